@@ -1,6 +1,11 @@
+use std::fs;
+use std::path::Path;
+use std::collections::HashMap;
+use anyhow::{ bail, Context, Result };
 use serde::{ Deserialize, Serialize };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub bot_name: String,
     pub concurrent_requests: usize,
@@ -10,6 +15,48 @@ pub struct Config {
     pub request_timeout: u64,
     pub max_retries: u32,
     pub retry_delay_ms: u64,
+    pub retry_max_delay_ms: u64,
+    pub blocked_cooloff_ms: u64,
+    pub job_dir: Option<String>,
+    pub resume: bool,
+    pub fingerprint_meta_keys: Vec<String>,
+    pub spider_middlewares: Vec<String>,
+    pub downloader_middlewares: Vec<String>,
+    pub default_headers: HashMap<String, String>,
+    pub li_at: Option<String>,
+    pub jsessionid: Option<String>,
+    pub cookie_file: Option<String>,
+    pub download_delay_ms: u64,
+    pub randomize_download_delay: bool,
+    pub rate_limit: Option<f64>,
+    pub rate_limit_burst: u32,
+    pub autothrottle_enabled: bool,
+    pub autothrottle_start_delay_ms: u64,
+    pub autothrottle_max_delay_ms: u64,
+    pub autothrottle_target_concurrency: f64,
+    pub proxies: Vec<String>,
+    pub proxy_file: Option<String>,
+    pub proxy_rotation: String,
+    pub proxy_max_failures: u32,
+    pub proxy_cooldown_ms: u64,
+    pub header_profiles_file: Option<String>,
+    pub header_profile_rotation: String,
+    pub http_cache_dir: Option<String>,
+    pub http_cache_expiration_secs: u64,
+    pub http_cache_gzip: bool,
+    pub offline: bool,
+    pub archive_dir: Option<String>,
+    pub selectors_file: Option<String>,
+    pub watch_selectors: bool,
+    pub coverage_baseline: Option<String>,
+    pub update_coverage_baseline: bool,
+    pub coverage_warn_drop: f64,
+    pub coverage_fail_drop: Option<f64>,
+    pub output_format: String,
+    pub csv_layout: String,
+    pub csv_max_entries: usize,
+    pub sqlite_path: Option<String>,
+    pub item_pipelines: Vec<String>,
 }
 
 impl Default for Config {
@@ -18,18 +65,99 @@ impl Default for Config {
             max_retries: 3,
             request_timeout: 30,
             retry_delay_ms: 1000,
+            retry_max_delay_ms: 60000,
+            blocked_cooloff_ms: 300000,
+            job_dir: None,
+            resume: false,
+            fingerprint_meta_keys: Vec::new(),
+            spider_middlewares: vec!["linkedin".to_string()],
+            downloader_middlewares: vec![
+                "http_cache".to_string(),
+                "ban_detection".to_string(),
+                "header_profiles".to_string(),
+                "default_headers".to_string(),
+                "user_agent".to_string(),
+                "session".to_string()
+            ],
+            default_headers: HashMap::from([
+                (
+                    "Accept".to_string(),
+                    "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8".to_string(),
+                ),
+                ("Accept-Language".to_string(), "en-US,en;q=0.9".to_string()),
+            ]),
+            li_at: None,
+            jsessionid: None,
+            cookie_file: None,
+            download_delay_ms: 0,
+            randomize_download_delay: true,
+            rate_limit: None,
+            rate_limit_burst: 1,
+            autothrottle_enabled: false,
+            autothrottle_start_delay_ms: 5000,
+            autothrottle_max_delay_ms: 60000,
+            autothrottle_target_concurrency: 1.0,
+            proxies: Vec::new(),
+            proxy_file: None,
+            proxy_rotation: "per_request".to_string(),
+            proxy_max_failures: 3,
+            proxy_cooldown_ms: 600000,
+            header_profiles_file: None,
+            header_profile_rotation: "sticky".to_string(),
+            http_cache_dir: None,
+            http_cache_expiration_secs: 0,
+            http_cache_gzip: false,
+            offline: false,
+            archive_dir: None,
+            selectors_file: None,
+            watch_selectors: false,
+            coverage_baseline: None,
+            update_coverage_baseline: false,
+            coverage_warn_drop: 10.0,
+            coverage_fail_drop: None,
+            output_format: "jsonl".to_string(),
+            csv_layout: "wide".to_string(),
+            csv_max_entries: 3,
+            sqlite_path: None,
+            item_pipelines: vec![
+                "normalize".to_string(),
+                "validate".to_string(),
+                "output".to_string()
+            ],
             robotstxt_obey: false,
             concurrent_requests: 1,
             output_dir: "data".to_string(),
             bot_name: "linkedin".to_string(),
-            user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36".to_string(),
+            user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".to_string(),
         }
     }
 }
 
 impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs
+            ::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") =>
+                toml
+                    ::from_str(&content)
+                    .with_context(|| format!("Invalid config file {}", path.display())),
+            Some("json") =>
+                serde_json
+                    ::from_str(&content)
+                    .with_context(|| format!("Invalid config file {}", path.display())),
+            _ => bail!("Config file {} must have a .toml or .json extension", path.display()),
+        }
+    }
+
     pub fn from_env() -> Self {
-        let mut config = Self::default();
+        Self::default().with_env()
+    }
+
+    pub fn with_env(self) -> Self {
+        let mut config = self;
 
         if let Ok(concurrent) = std::env::var("CONCURRENT_REQUESTS") {
             if let Ok(num) = concurrent.parse() {
@@ -55,6 +183,210 @@ impl Config {
             }
         }
 
+        if let Ok(max_delay) = std::env::var("RETRY_MAX_DELAY_MS") {
+            if let Ok(num) = max_delay.parse() {
+                config.retry_max_delay_ms = num;
+            }
+        }
+
+        if let Ok(cooloff) = std::env::var("BLOCKED_COOLOFF_MS") {
+            if let Ok(num) = cooloff.parse() {
+                config.blocked_cooloff_ms = num;
+            }
+        }
+
+        if let Ok(obey) = std::env::var("ROBOTSTXT_OBEY") {
+            if let Ok(value) = obey.parse() {
+                config.robotstxt_obey = value;
+            }
+        }
+
+        if let Ok(job_dir) = std::env::var("JOB_DIR") {
+            config.job_dir = Some(job_dir);
+        }
+
+        if let Ok(meta_keys) = std::env::var("FINGERPRINT_META_KEYS") {
+            config.fingerprint_meta_keys = parse_list(&meta_keys);
+        }
+
+        if let Ok(middlewares) = std::env::var("SPIDER_MIDDLEWARES") {
+            config.spider_middlewares = parse_list(&middlewares);
+        }
+
+        if let Ok(middlewares) = std::env::var("DOWNLOADER_MIDDLEWARES") {
+            config.downloader_middlewares = parse_list(&middlewares);
+        }
+
+        if let Ok(li_at) = std::env::var("LINKEDIN_COOKIE_LI_AT") {
+            config.li_at = Some(li_at);
+        }
+
+        if let Ok(jsessionid) = std::env::var("LINKEDIN_COOKIE_JSESSIONID") {
+            config.jsessionid = Some(jsessionid);
+        }
+
+        if let Ok(cookie_file) = std::env::var("LINKEDIN_COOKIE_FILE") {
+            config.cookie_file = Some(cookie_file);
+        }
+
+        if let Ok(delay) = std::env::var("DOWNLOAD_DELAY_MS") {
+            if let Ok(value) = delay.parse() {
+                config.download_delay_ms = value;
+            }
+        }
+
+        if let Ok(randomize) = std::env::var("RANDOMIZE_DOWNLOAD_DELAY") {
+            if let Ok(value) = randomize.parse() {
+                config.randomize_download_delay = value;
+            }
+        }
+
+        if let Ok(rate) = std::env::var("RATE_LIMIT") {
+            config.rate_limit = rate.parse().ok();
+        }
+
+        if let Ok(burst) = std::env::var("RATE_LIMIT_BURST") {
+            if let Ok(value) = burst.parse() {
+                config.rate_limit_burst = value;
+            }
+        }
+
+        if let Ok(enabled) = std::env::var("AUTOTHROTTLE_ENABLED") {
+            if let Ok(value) = enabled.parse() {
+                config.autothrottle_enabled = value;
+            }
+        }
+
+        if let Ok(delay) = std::env::var("AUTOTHROTTLE_START_DELAY_MS") {
+            if let Ok(value) = delay.parse() {
+                config.autothrottle_start_delay_ms = value;
+            }
+        }
+
+        if let Ok(delay) = std::env::var("AUTOTHROTTLE_MAX_DELAY_MS") {
+            if let Ok(value) = delay.parse() {
+                config.autothrottle_max_delay_ms = value;
+            }
+        }
+
+        if let Ok(concurrency) = std::env::var("AUTOTHROTTLE_TARGET_CONCURRENCY") {
+            if let Ok(value) = concurrency.parse() {
+                config.autothrottle_target_concurrency = value;
+            }
+        }
+
+        if let Ok(proxies) = std::env::var("PROXIES") {
+            config.proxies = parse_list(&proxies);
+        }
+
+        if let Ok(proxy_file) = std::env::var("PROXY_FILE") {
+            config.proxy_file = Some(proxy_file);
+        }
+
+        if let Ok(rotation) = std::env::var("PROXY_ROTATION") {
+            config.proxy_rotation = rotation;
+        }
+
+        if let Ok(max_failures) = std::env::var("PROXY_MAX_FAILURES") {
+            if let Ok(num) = max_failures.parse() {
+                config.proxy_max_failures = num;
+            }
+        }
+
+        if let Ok(cooldown) = std::env::var("PROXY_COOLDOWN_MS") {
+            if let Ok(num) = cooldown.parse() {
+                config.proxy_cooldown_ms = num;
+            }
+        }
+
+        if let Ok(profiles_file) = std::env::var("HEADER_PROFILES_FILE") {
+            config.header_profiles_file = Some(profiles_file);
+        }
+
+        if let Ok(rotation) = std::env::var("HEADER_PROFILE_ROTATION") {
+            config.header_profile_rotation = rotation;
+        }
+
+        if let Ok(cache_dir) = std::env::var("HTTPCACHE_DIR") {
+            config.http_cache_dir = Some(cache_dir);
+        }
+
+        if let Ok(expiration) = std::env::var("HTTPCACHE_EXPIRATION_SECS") {
+            if let Ok(secs) = expiration.parse() {
+                config.http_cache_expiration_secs = secs;
+            }
+        }
+
+        if let Ok(gzip) = std::env::var("HTTPCACHE_GZIP") {
+            if let Ok(value) = gzip.parse() {
+                config.http_cache_gzip = value;
+            }
+        }
+
+        if let Ok(offline) = std::env::var("OFFLINE") {
+            if let Ok(value) = offline.parse() {
+                config.offline = value;
+            }
+        }
+
+        if let Ok(archive_dir) = std::env::var("ARCHIVE_RESPONSES_DIR") {
+            config.archive_dir = Some(archive_dir);
+        }
+
+        if let Ok(selectors_file) = std::env::var("SELECTORS_FILE") {
+            config.selectors_file = Some(selectors_file);
+        }
+
+        if let Ok(watch) = std::env::var("WATCH_SELECTORS") {
+            if let Ok(value) = watch.parse() {
+                config.watch_selectors = value;
+            }
+        }
+
+        if let Ok(baseline) = std::env::var("COVERAGE_BASELINE") {
+            config.coverage_baseline = Some(baseline);
+        }
+
+        if let Ok(update) = std::env::var("UPDATE_COVERAGE_BASELINE") {
+            if let Ok(value) = update.parse() {
+                config.update_coverage_baseline = value;
+            }
+        }
+
+        if let Ok(warn_drop) = std::env::var("COVERAGE_WARN_DROP") {
+            if let Ok(value) = warn_drop.parse() {
+                config.coverage_warn_drop = value;
+            }
+        }
+
+        if let Ok(fail_drop) = std::env::var("COVERAGE_FAIL_DROP") {
+            if let Ok(value) = fail_drop.parse() {
+                config.coverage_fail_drop = Some(value);
+            }
+        }
+
+        if let Ok(format) = std::env::var("OUTPUT_FORMAT") {
+            config.output_format = format;
+        }
+
+        if let Ok(layout) = std::env::var("CSV_LAYOUT") {
+            config.csv_layout = layout;
+        }
+
+        if let Ok(max_entries) = std::env::var("CSV_MAX_ENTRIES") {
+            if let Ok(value) = max_entries.parse() {
+                config.csv_max_entries = value;
+            }
+        }
+
+        if let Ok(sqlite_path) = std::env::var("SQLITE_PATH") {
+            config.sqlite_path = Some(sqlite_path);
+        }
+
+        if let Ok(pipelines) = std::env::var("ITEM_PIPELINES") {
+            config.item_pipelines = parse_list(&pipelines);
+        }
+
         if let Ok(user_agent) = std::env::var("USER_AGENT") {
             config.user_agent = user_agent;
         }
@@ -62,3 +394,43 @@ impl Config {
         config
     }
 }
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(extension: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("in-scraper-config-{}.{extension}", std::process::id()))
+    }
+
+    #[test]
+    fn loads_partial_toml_config_over_defaults() {
+        let path = temp_path("toml");
+        fs::write(&path, "li_at = \"AQEDAT\"\nconcurrent_requests = 4\n").unwrap();
+        let config = Config::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.li_at.as_deref(), Some("AQEDAT"));
+        assert_eq!(config.concurrent_requests, 4);
+        assert_eq!(config.output_dir, Config::default().output_dir);
+    }
+
+    #[test]
+    fn rejects_unknown_config_entries() {
+        let path = temp_path("json");
+        fs::write(&path, r#"{ "li-at": "AQEDAT" }"#).unwrap();
+        let config = Config::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(config.is_err());
+    }
+}
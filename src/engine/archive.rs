@@ -0,0 +1,116 @@
+use std::fs;
+use tokio::task;
+use crate::config::Config;
+use chrono::{ DateTime, Utc };
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use serde_json::Value;
+use anyhow::{ Context, Result };
+use schemars::{ schema_for, JsonSchema, Schema };
+use serde::{ Deserialize, Serialize };
+use crate::spiders::{ Request, Response };
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveRecord {
+    pub id: String,
+    pub spider: String,
+    pub url: String,
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub archived_at: DateTime<Utc>,
+    pub body_file: String,
+    pub request: Request,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct CrawledItem<T: Serialize> {
+    #[serde(flatten)]
+    pub item: T,
+    pub crawled_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ArchivedItem<T: Serialize> {
+    #[serde(flatten)]
+    pub item: T,
+    pub response_id: String,
+    pub crawled_at: DateTime<Utc>,
+}
+
+impl<T: Serialize + JsonSchema> ArchivedItem<T> {
+    pub fn schema(archived: bool) -> Schema {
+        if archived { schema_for!(ArchivedItem<T>) } else { schema_for!(CrawledItem<T>) }
+    }
+
+    pub fn to_value(
+        item: T,
+        response_id: Option<String>,
+        crawled_at: DateTime<Utc>
+    ) -> Result<Value> {
+        let value = match response_id {
+            Some(response_id) =>
+                serde_json::to_value(ArchivedItem { item, response_id, crawled_at }),
+            None => serde_json::to_value(CrawledItem { item, crawled_at }),
+        };
+        value.context("Failed to serialize item")
+    }
+}
+
+pub struct ResponseArchive {
+    dir: PathBuf,
+    meta_keys: Vec<String>,
+}
+
+impl ResponseArchive {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.archive_dir.as_ref().map(|dir| Self {
+            dir: PathBuf::from(dir),
+            meta_keys: config.fingerprint_meta_keys.clone(),
+        })
+    }
+
+    pub async fn store(
+        &self,
+        spider_name: &str,
+        request: &Request,
+        response: &Response
+    ) -> Result<String> {
+        let archived_at = Utc::now();
+        let fingerprint = request.fingerprint(&self.meta_keys);
+        let id = format!("{}-{}", archived_at.format("%Y%m%dT%H%M%S%3fZ"), &fingerprint[..12]);
+
+        let dir = self.dir.join(spider_name);
+        let body = response.body.clone();
+        let record = ArchiveRecord {
+            id: id.clone(),
+            spider: spider_name.to_string(),
+            url: response.url.clone(),
+            status: response.status,
+            headers: response.headers.clone(),
+            archived_at,
+            body_file: format!("{id}.html"),
+            request: request.clone(),
+        };
+        task::spawn_blocking(move || Self::write(&dir, &body, &record))
+            .await
+            .context("Archive writer task failed")??;
+
+        Ok(id)
+    }
+
+    fn write(dir: &Path, body: &str, record: &ArchiveRecord) -> Result<()> {
+        fs
+            ::create_dir_all(dir)
+            .with_context(|| format!("Failed to create archive directory {}", dir.display()))?;
+        fs::write(dir.join(&record.body_file), body).context("Failed to archive response body")?;
+
+        let json = serde_json::to_string_pretty(record)?;
+        let path = dir.join(format!("{}.json", record.id));
+        fs::write(path, json).context("Failed to archive response metadata")
+    }
+
+    pub fn load_record(body_path: &Path) -> Option<ArchiveRecord> {
+        let content = fs::read_to_string(body_path.with_extension("json")).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+}
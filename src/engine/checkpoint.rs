@@ -0,0 +1,296 @@
+use std::sync::Mutex;
+use std::io::{ BufRead, BufReader, Write };
+use crate::spiders::Request;
+use anyhow::{ bail, Context, Result };
+use std::fs::{ self, File, OpenOptions };
+use serde::{ Deserialize, Serialize };
+use std::path::{ Path, PathBuf };
+use std::collections::{ HashMap, HashSet };
+
+const STATE_FILE: &str = "state.json";
+const REQUESTS_FILE: &str = "requests.jsonl";
+const COMPLETED_FILE: &str = "completed.txt";
+const FAILED_FILE: &str = "failed.jsonl";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrawlState {
+    pub spider: String,
+    pub output_files: HashMap<String, PathBuf>,
+}
+
+pub struct JobDir {
+    path: PathBuf,
+    meta_keys: Vec<String>,
+    state: Mutex<CrawlState>,
+    requests_log: Mutex<File>,
+    completed_log: Mutex<File>,
+    failed_log: Mutex<File>,
+}
+
+#[derive(Serialize)]
+struct FailedRequest<'a> {
+    request: &'a Request,
+    error: &'a str,
+}
+
+impl JobDir {
+    pub fn create(
+        path: impl Into<PathBuf>,
+        spider_name: &str,
+        meta_keys: Vec<String>
+    ) -> Result<Self> {
+        let path = path.into();
+        if path.join(STATE_FILE).exists() {
+            bail!("Job directory {} already contains a crawl, use --resume", path.display());
+        }
+        fs::create_dir_all(&path).context("Failed to create job directory")?;
+
+        let job_dir = Self::open_logs(path, meta_keys, CrawlState {
+            spider: spider_name.to_string(),
+            ..Default::default()
+        })?;
+        job_dir.save_state()?;
+        Ok(job_dir)
+    }
+
+    pub fn resume(
+        path: impl Into<PathBuf>,
+        spider_name: &str,
+        meta_keys: Vec<String>
+    ) -> Result<Self> {
+        let path = path.into();
+        let state_path = path.join(STATE_FILE);
+        let content = fs
+            ::read_to_string(&state_path)
+            .with_context(|| format!("Failed to read {}", state_path.display()))?;
+        let state: CrawlState = serde_json
+            ::from_str(&content)
+            .context("Failed to parse crawl state")?;
+
+        if state.spider != spider_name {
+            bail!(
+                "Job directory {} belongs to spider {}, not {}",
+                path.display(),
+                state.spider,
+                spider_name
+            );
+        }
+
+        Self::open_logs(path, meta_keys, state)
+    }
+
+    fn open_logs(path: PathBuf, meta_keys: Vec<String>, state: CrawlState) -> Result<Self> {
+        let open = |name: &str| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path.join(name))
+                .with_context(|| format!("Failed to open {name}"))
+        };
+
+        Ok(Self {
+            requests_log: Mutex::new(open(REQUESTS_FILE)?),
+            completed_log: Mutex::new(open(COMPLETED_FILE)?),
+            failed_log: Mutex::new(open(FAILED_FILE)?),
+            state: Mutex::new(state),
+            meta_keys,
+            path,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn state(&self) -> CrawlState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn completed(&self) -> Result<HashSet<String>> {
+        let file = File::open(self.path.join(COMPLETED_FILE)).context(
+            "Failed to open completed log"
+        )?;
+
+        let mut completed = HashSet::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.context("Failed to read completed log")?;
+            if !line.trim().is_empty() {
+                completed.insert(line.trim().to_string());
+            }
+        }
+        Ok(completed)
+    }
+
+    pub fn pending(&self, completed: &HashSet<String>) -> Result<Vec<Request>> {
+        let file = File::open(self.path.join(REQUESTS_FILE)).context(
+            "Failed to open requests log"
+        )?;
+
+        let mut seen = HashSet::new();
+        let mut pending = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.context("Failed to read requests log")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // A crash can leave the last line half-written
+            let Ok(request) = serde_json::from_str::<Request>(&line) else {
+                continue;
+            };
+            let fingerprint = request.fingerprint(&self.meta_keys);
+            if !completed.contains(&fingerprint) && seen.insert(fingerprint) {
+                pending.push(request);
+            }
+        }
+        Ok(pending)
+    }
+
+    pub fn record_scheduled(&self, requests: &[Request]) -> Result<()> {
+        let mut file = self.requests_log.lock().unwrap();
+        for request in requests {
+            let line = serde_json::to_string(request).context("Failed to serialize request")?;
+            writeln!(file, "{line}").context("Failed to write requests log")?;
+        }
+        file.flush().context("Failed to flush requests log")?;
+        Ok(())
+    }
+
+    pub fn record_completed(&self, request: &Request) -> Result<()> {
+        let fingerprint = request.fingerprint(&self.meta_keys);
+        let mut file = self.completed_log.lock().unwrap();
+        writeln!(file, "{fingerprint}").context("Failed to write completed log")?;
+        file.flush().context("Failed to flush completed log")?;
+        Ok(())
+    }
+
+    pub fn record_failed(&self, request: &Request, error: &str) -> Result<()> {
+        {
+            let record = FailedRequest { request, error };
+            let line = serde_json::to_string(&record).context("Failed to serialize request")?;
+            let mut file = self.failed_log.lock().unwrap();
+            writeln!(file, "{line}").context("Failed to write failed log")?;
+            file.flush().context("Failed to flush failed log")?;
+        }
+        self.record_completed(request)
+    }
+
+    pub fn record_output_files(&self, output_files: HashMap<String, PathBuf>) -> Result<()> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.output_files == output_files {
+                return Ok(());
+            }
+            state.output_files = output_files;
+        }
+        self.save_state()
+    }
+
+    fn save_state(&self) -> Result<()> {
+        let content = serde_json
+            ::to_string_pretty(&*self.state.lock().unwrap())
+            .context("Failed to serialize crawl state")?;
+
+        let tmp_path = self.path.join(format!("{STATE_FILE}.tmp"));
+        fs::write(&tmp_path, content).context("Failed to write crawl state")?;
+        fs::rename(&tmp_path, self.path.join(STATE_FILE)).context("Failed to save crawl state")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir_name = format!("in-scraper-job-{name}-{}", std::process::id());
+        let path = std::env::temp_dir().join(dir_name);
+        let _ = fs::remove_dir_all(&path);
+        path
+    }
+
+    fn request(url: &str) -> Request {
+        Request::new(url.to_string())
+    }
+
+    #[test]
+    fn pending_skips_completed_and_repeated_requests() {
+        let path = temp_dir("pending");
+        let job_dir = JobDir::create(&path, "jobs", Vec::new()).unwrap();
+        job_dir.record_scheduled(&[request("https://a/1"), request("https://a/2")]).unwrap();
+        job_dir.record_scheduled(&[request("https://a/3"), request("https://a/2")]).unwrap();
+        job_dir.record_completed(&request("https://a/1")).unwrap();
+        drop(job_dir);
+
+        let job_dir = JobDir::resume(&path, "jobs", Vec::new()).unwrap();
+        let completed = job_dir.completed().unwrap();
+        let pending = job_dir.pending(&completed).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+
+        let urls: Vec<&str> = pending.iter().map(|request| request.url.as_str()).collect();
+        assert_eq!(urls, ["https://a/2", "https://a/3"]);
+        assert_eq!(completed.len(), 1);
+    }
+
+    #[test]
+    fn pending_ignores_a_truncated_last_line() {
+        let path = temp_dir("truncated");
+        let job_dir = JobDir::create(&path, "jobs", Vec::new()).unwrap();
+        job_dir.record_scheduled(&[request("https://a/1")]).unwrap();
+        drop(job_dir);
+        let mut log = OpenOptions::new().append(true).open(path.join(REQUESTS_FILE)).unwrap();
+        write!(log, "{{\"url\":\"https://a/2\",\"me").unwrap();
+
+        let job_dir = JobDir::resume(&path, "jobs", Vec::new()).unwrap();
+        let pending = job_dir.pending(&HashSet::new()).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].url, "https://a/1");
+    }
+
+    #[test]
+    fn resume_rejects_another_spider_and_create_rejects_an_existing_crawl() {
+        let path = temp_dir("mismatch");
+        JobDir::create(&path, "jobs", Vec::new()).unwrap();
+
+        let resumed = JobDir::resume(&path, "people", Vec::new());
+        let created = JobDir::create(&path, "jobs", Vec::new());
+        fs::remove_dir_all(&path).unwrap();
+
+        let error = resumed.err().unwrap().to_string();
+        assert!(error.contains("belongs to spider jobs"), "{error}");
+        assert!(created.is_err());
+    }
+
+    #[test]
+    fn resume_keeps_output_files() {
+        let path = temp_dir("outputs");
+        let job_dir = JobDir::create(&path, "jobs", Vec::new()).unwrap();
+        let output_files = HashMap::from([("jobs".to_string(), PathBuf::from("data/jobs.jsonl"))]);
+        job_dir.record_output_files(output_files.clone()).unwrap();
+        drop(job_dir);
+
+        let state = JobDir::resume(&path, "jobs", Vec::new()).unwrap().state();
+        fs::remove_dir_all(&path).unwrap();
+        assert_eq!(state.output_files, output_files);
+    }
+
+    #[test]
+    fn failed_requests_are_logged_and_not_resumed() {
+        let path = temp_dir("failed");
+        let job_dir = JobDir::create(&path, "jobs", Vec::new()).unwrap();
+        job_dir.record_scheduled(&[request("https://a/1"), request("https://a/2")]).unwrap();
+        job_dir.record_failed(&request("https://a/2"), "HTTP 500").unwrap();
+        drop(job_dir);
+
+        let job_dir = JobDir::resume(&path, "jobs", Vec::new()).unwrap();
+        let pending = job_dir.pending(&job_dir.completed().unwrap()).unwrap();
+        let failed = fs::read_to_string(path.join(FAILED_FILE)).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(pending.len(), 1);
+        let failed: serde_json::Value = serde_json::from_str(failed.trim()).unwrap();
+        assert_eq!(failed["request"]["url"], "https://a/2");
+        assert_eq!(failed["error"], "HTTP 500");
+    }
+}
@@ -0,0 +1,206 @@
+use std::fs;
+use std::sync::Mutex;
+use serde_json::Value;
+use std::path::{ Path, PathBuf };
+use crate::config::Config;
+use chrono::{ DateTime, Utc };
+use tracing::{ info, warn };
+use std::collections::BTreeMap;
+use anyhow::{ bail, Context, Result };
+use serde::{ Deserialize, Serialize };
+use crate::engine::Stats;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct FieldCount {
+    present: u64,
+    total: u64,
+}
+
+#[derive(Default)]
+pub struct FieldCoverage {
+    items: Mutex<u64>,
+    empty_pages: Mutex<u64>,
+    fields: Mutex<BTreeMap<String, FieldCount>>,
+}
+
+impl FieldCoverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe<T: Serialize>(&self, item: &T) {
+        let Ok(Value::Object(object)) = serde_json::to_value(item) else {
+            return;
+        };
+
+        *self.items.lock().unwrap() += 1;
+        let mut fields = self.fields.lock().unwrap();
+        for (name, value) in &object {
+            Self::count(&mut fields, name.clone(), value);
+
+            let Value::Array(entries) = value else {
+                continue;
+            };
+            for entry in entries {
+                for (child, value) in entry.as_object().into_iter().flatten() {
+                    Self::count(&mut fields, format!("{name}[].{child}"), value);
+                }
+            }
+        }
+    }
+
+    pub fn observe_empty_page(&self) {
+        *self.empty_pages.lock().unwrap() += 1;
+    }
+
+    fn count(fields: &mut BTreeMap<String, FieldCount>, name: String, value: &Value) {
+        let count = fields.entry(name).or_default();
+        count.total += 1;
+        if Self::is_present(value) {
+            count.present += 1;
+        }
+    }
+
+    fn is_present(value: &Value) -> bool {
+        match value {
+            Value::Null => false,
+            Value::String(text) => !text.trim().is_empty(),
+            Value::Array(entries) => !entries.is_empty(),
+            Value::Object(object) => !object.is_empty(),
+            _ => true,
+        }
+    }
+
+    pub fn report(&self) -> CoverageReport {
+        let fields = self.fields.lock().unwrap();
+        let empty_pages = *self.empty_pages.lock().unwrap();
+        CoverageReport {
+            items: *self.items.lock().unwrap(),
+            empty_pages,
+            recorded_at: Utc::now(),
+            fields: fields
+                .iter()
+                .map(|(name, count)| {
+                    let total = if name.contains("[]") {
+                        count.total
+                    } else {
+                        count.total + empty_pages
+                    };
+                    (name.clone(), (count.present as f64) / (total as f64))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn finish(&self, spider_name: &str, config: &Config, stats: &Stats) -> Result<()> {
+        let report = self.report();
+        stats.set_value("coverage/item_count", report.items);
+        stats.set_value("coverage/empty_page_count", report.empty_pages);
+        for (name, count) in self.fields.lock().unwrap().iter() {
+            stats.set_value(&format!("coverage/{name}"), count.present);
+        }
+
+        info!(
+            "Field coverage for spider {} ({} items, {} empty pages):",
+            spider_name,
+            report.items,
+            report.empty_pages
+        );
+        for (name, rate) in &report.fields {
+            info!("  {}: {:.1}%", name, rate * 100.0);
+        }
+
+        let Some(path) = &config.coverage_baseline else {
+            return Ok(());
+        };
+        let path = PathBuf::from(path);
+        let mut baselines = CoverageBaselines::load(&path)?;
+
+        let failures = match baselines.spiders.get(spider_name) {
+            Some(baseline) => Self::compare(spider_name, baseline, &report, config),
+            None => Vec::new(),
+        };
+
+        let record = config.update_coverage_baseline || !baselines.spiders.contains_key(spider_name);
+        if record && failures.is_empty() && report.items > 0 {
+            baselines.spiders.insert(spider_name.to_string(), report);
+            baselines.save(&path)?;
+            info!("Coverage baseline for {} saved to {}", spider_name, path.display());
+        }
+
+        if !failures.is_empty() {
+            bail!(
+                "Field coverage dropped below baseline for {}: {}",
+                spider_name,
+                failures.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    fn compare(
+        spider_name: &str,
+        baseline: &CoverageReport,
+        report: &CoverageReport,
+        config: &Config
+    ) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        for (name, expected) in &baseline.fields {
+            let actual = match report.fields.get(name) {
+                Some(actual) => *actual,
+                None if name.contains("[]") => {
+                    continue;
+                }
+                None => 0.0,
+            };
+            let drop = (expected - actual) * 100.0;
+            let summary = format!("{name} {:.1}% -> {:.1}%", expected * 100.0, actual * 100.0);
+
+            if config.coverage_fail_drop.is_some_and(|threshold| drop > threshold) {
+                failures.push(summary);
+            } else if drop > config.coverage_warn_drop {
+                warn!("Field coverage for {} dropped: {}", spider_name, summary);
+            }
+        }
+        failures
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub items: u64,
+    #[serde(default)]
+    pub empty_pages: u64,
+    pub recorded_at: DateTime<Utc>,
+    pub fields: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CoverageBaselines {
+    pub spiders: BTreeMap<String, CoverageReport>,
+}
+
+impl CoverageBaselines {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs
+            ::read_to_string(path)
+            .with_context(|| format!("Failed to read coverage baseline {}", path.display()))?;
+        serde_json
+            ::from_str(&content)
+            .with_context(|| format!("Invalid coverage baseline {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs
+            ::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write coverage baseline {}", path.display()))
+    }
+}
@@ -0,0 +1,532 @@
+pub mod stats;
+pub mod archive;
+pub mod coverage;
+pub mod offline;
+pub mod throttle;
+pub mod scheduler;
+pub mod checkpoint;
+
+pub use stats::Stats;
+pub use archive::{ ArchiveRecord, ArchivedItem, CrawledItem, ResponseArchive };
+pub use coverage::{ CoverageBaselines, CoverageReport, FieldCoverage };
+pub use offline::parse_files;
+pub use throttle::Throttle;
+pub use scheduler::Scheduler;
+pub use checkpoint::{ CrawlState, JobDir };
+
+use chrono::Utc;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{ sleep_until, Instant };
+use std::path::PathBuf;
+use tokio::sync::Semaphore;
+use crate::utils::RobotsCache;
+use crate::error::ScrapeError;
+use crate::utils::RetryDecision;
+use crate::pipeline::ItemPipelineChain;
+use crate::middleware::{ Downloader, SpiderMiddlewareChain, SpiderOutput };
+use tracing::{ info, warn, error };
+use crate::spiders::{ Request, Spider };
+use std::sync::atomic::{ AtomicBool, Ordering };
+
+const RETRY_TIMES_META: &str = "retry_times";
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct TaskOutput {
+    requests: Vec<Request>,
+    retry: Option<(Request, Duration)>,
+}
+
+fn open_job_dir<S: Spider>(spider: &S) -> Result<Option<JobDir>> {
+    let config = spider.get_config();
+
+    if config.resume {
+        let path = config.job_dir
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Resuming requires a job directory"))?;
+        return JobDir::resume(path, spider.name(), config.fingerprint_meta_keys.clone()).map(Some);
+    }
+
+    match &config.job_dir {
+        Some(path) =>
+            JobDir::create(
+                PathBuf::from(path),
+                spider.name(),
+                config.fingerprint_meta_keys.clone()
+            ).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn checkpoint(job_dir: &Option<Arc<JobDir>>, record: impl FnOnce(&JobDir) -> Result<()>) {
+    if let Some(job_dir) = job_dir {
+        if let Err(e) = record(job_dir) {
+            error!("Checkpoint error: {}", e);
+        }
+    }
+}
+
+pub async fn run_spider<S: Spider + 'static>(
+    spider: S,
+    pipeline: Arc<ItemPipelineChain>
+) -> Result<()> {
+    info!("Starting spider: {}", spider.name());
+
+    let stats = Arc::new(Stats::new());
+    let coverage = Arc::new(FieldCoverage::new());
+    let robots = if spider.get_config().robotstxt_obey && !spider.get_config().offline {
+        Some(
+            Arc::new(RobotsCache::new(spider.get_config().clone(), spider.get_http_client().clone()))
+        )
+    } else {
+        None
+    };
+
+    let middlewares = Arc::new(SpiderMiddlewareChain::from_config(spider.get_config())?);
+    let mut downloader = Downloader::from_config(
+        spider.get_config().clone(),
+        spider.get_http_client().clone(),
+        stats.clone()
+    )?;
+    for middleware in spider.downloader_middlewares() {
+        downloader.push_boxed(middleware);
+    }
+    let archived = match ResponseArchive::from_config(spider.get_config()) {
+        Some(archive) => {
+            downloader = downloader.with_archive(archive, stats.clone());
+            true
+        }
+        None => false,
+    };
+    let downloader = Arc::new(downloader);
+    let throttle = Arc::new(Throttle::new(spider.get_config()));
+    let job_dir = open_job_dir(&spider)?.map(Arc::new);
+    if let Some(job_dir) = &job_dir {
+        info!("Crawl state is saved to {}", job_dir.path().display());
+    }
+
+    let mut scheduler = Scheduler::new(
+        spider.get_config().fingerprint_meta_keys.clone(),
+        stats.clone()
+    );
+    let mut resumed = false;
+    if let Some(job_dir) = job_dir.as_ref().filter(|_| spider.get_config().resume) {
+        let completed = job_dir.completed()?;
+        let pending = job_dir.pending(&completed)?;
+        pipeline.resume_output_files(job_dir.state().output_files).await;
+        info!("Resuming crawl: {} requests completed, {} pending", completed.len(), pending.len());
+
+        resumed = !completed.is_empty() || !pending.is_empty();
+        scheduler.mark_seen(completed);
+        scheduler.enqueue_all(pending);
+    }
+
+    if !resumed {
+        let start_requests = spider.start_requests().await;
+        if let Some(job_dir) = &job_dir {
+            job_dir.record_scheduled(&start_requests)?;
+        }
+        scheduler.enqueue_all(start_requests);
+    }
+
+    let schema = ArchivedItem::<S::Item>::schema(archived);
+    pipeline.open_spider(spider.name(), &schema).await?;
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_listener = {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                warn!("Interrupted, finishing in-flight requests. Press Ctrl-C again to exit now");
+                shutdown.store(true, Ordering::SeqCst);
+            }
+            if tokio::signal::ctrl_c().await.is_ok() {
+                warn!("Interrupted again, exiting");
+                std::process::exit(130);
+            }
+        })
+    };
+
+    let semaphore = Arc::new(Semaphore::new(spider.get_config().concurrent_requests));
+    let mut handles = vec![];
+
+    while (!scheduler.is_empty() && !shutdown.load(Ordering::SeqCst)) || !handles.is_empty() {
+        while !shutdown.load(Ordering::SeqCst) {
+            let Some(request) = scheduler.next_request() else {
+                break;
+            };
+
+            let spider_clone = spider.clone();
+            let pipeline_clone = pipeline.clone();
+            let semaphore_clone = semaphore.clone();
+            let robots_clone = robots.clone();
+            let stats_clone = stats.clone();
+            let coverage_clone = coverage.clone();
+            let job_dir_clone = job_dir.clone();
+            let shutdown_clone = shutdown.clone();
+            let middlewares_clone = middlewares.clone();
+            let downloader_clone = downloader.clone();
+            let throttle_clone = throttle.clone();
+
+            let handle = tokio::spawn(async move {
+                let _permit = semaphore_clone.acquire().await.unwrap();
+
+                if shutdown_clone.load(Ordering::SeqCst) {
+                    return TaskOutput::default();
+                }
+
+                if let Some(robots) = &robots_clone {
+                    if !robots.is_allowed(&request.url).await {
+                        warn!("Forbidden by robots.txt: {}", request.url);
+                        stats_clone.inc_value("robotstxt/forbidden");
+                        checkpoint(&job_dir_clone, |job_dir| job_dir.record_completed(&request));
+                        return TaskOutput::default();
+                    }
+                    robots.wait_crawl_delay(&request.url).await;
+                }
+
+                stats_clone.inc_value("request_count");
+
+                let spider_name = spider_clone.name();
+                let mut response_id = None;
+                let mut crawled_at = Utc::now();
+                let result = async {
+                    let waited = throttle_clone.acquire(&request.url).await;
+                    stats_clone.inc_value_by("throttle/wait_ms", waited.as_millis() as u64);
+
+                    let started = Instant::now();
+                    let fetched = downloader_clone.fetch(&request, spider_name).await;
+                    let status = match &fetched {
+                        Ok(response) => Some(response.status),
+                        Err(e) => e.downcast_ref::<ScrapeError>().and_then(ScrapeError::status),
+                    };
+                    throttle_clone.record_response(&request.url, started.elapsed(), status).await;
+                    let response = fetched?;
+                    crawled_at = Utc::now();
+                    if let Some(proxy) = &response.proxy {
+                        stats_clone.inc_value(&format!("proxy/{proxy}/response_count"));
+                    }
+                    response_id = response.archive_id.clone();
+                    middlewares_clone.process_spider_input(&response, &request, spider_name).await?;
+                    let (items, next_requests) = spider_clone.parse(response.body, &request).await?;
+
+                    let output = items
+                        .into_iter()
+                        .map(SpiderOutput::Item)
+                        .chain(next_requests.into_iter().map(SpiderOutput::Request))
+                        .collect();
+                    middlewares_clone.process_spider_output(output, &request, spider_name).await
+                }.await;
+
+                let output = match result {
+                    Ok(output) => Ok(output),
+                    Err(e) => {
+                        match
+                            middlewares_clone.process_spider_exception(
+                                &e,
+                                &request,
+                                spider_name
+                            ).await
+                        {
+                            Ok(Some(output)) => Ok(output),
+                            Ok(None) => Err(e),
+                            Err(middleware_error) => Err(middleware_error),
+                        }
+                    }
+                };
+
+                match output {
+                    Ok(output) => {
+                        let mut next_requests = Vec::new();
+                        for entry in output {
+                            let item = match entry {
+                                SpiderOutput::Item(item) => {
+                                    coverage_clone.observe(&item);
+                                    item
+                                }
+                                SpiderOutput::Request(next_request) => {
+                                    next_requests.push(next_request);
+                                    continue;
+                                }
+                            };
+                            let response_id = response_id.clone();
+                            let item = ArchivedItem::to_value(item, response_id, crawled_at);
+                            let processed = match item {
+                                Ok(item) => pipeline_clone.process_item(item, spider_name).await,
+                                Err(e) => Err(e),
+                            };
+                            match processed {
+                                Ok(Some(_)) => stats_clone.inc_value("item_scraped_count"),
+                                Ok(None) => stats_clone.inc_value("item_dropped_count"),
+                                Err(e) => {
+                                    error!("Pipeline error: {}", e);
+                                    stats_clone.inc_value("item_error_count");
+                                }
+                            }
+                        }
+                        checkpoint(&job_dir_clone, |job_dir| job_dir.record_scheduled(&next_requests));
+                        checkpoint(&job_dir_clone, |job_dir| job_dir.record_completed(&request));
+                        TaskOutput { requests: next_requests, retry: None }
+                    }
+                    Err(e) => {
+                        let Some(scrape_error) = e.downcast_ref::<ScrapeError>() else {
+                            error!("Spider error: {}", e);
+                            stats_clone.inc_value("spider_error_count");
+                            let error = format!("{e:#}");
+                            checkpoint(&job_dir_clone, |job_dir| {
+                                job_dir.record_failed(&request, &error)
+                            });
+                            return TaskOutput::default();
+                        };
+
+                        stats_clone.inc_value(&format!("scrape_error/{}", scrape_error.kind()));
+                        if let ScrapeError::EmptyPage { .. } = scrape_error {
+                            coverage_clone.observe_empty_page();
+                        }
+
+                        if scrape_error.is_fatal() {
+                            error!("{}, stopping crawl", scrape_error);
+                            shutdown_clone.store(true, Ordering::SeqCst);
+                            return TaskOutput::default();
+                        }
+
+                        let retry_times = request.meta
+                            .get(RETRY_TIMES_META)
+                            .and_then(|value| value.parse::<u32>().ok())
+                            .unwrap_or(0);
+
+                        let retry_policy = spider_clone.get_http_client().retry_policy();
+                        let RetryDecision::Retry(delay) = retry_policy.on_scrape_error(
+                            retry_times,
+                            scrape_error
+                        ) else {
+                            error!("Giving up on {}: {}", request.url, scrape_error);
+                            if retry_times >= retry_policy.max_retries() {
+                                stats_clone.inc_value("retry/max_reached");
+                            }
+                            let error = scrape_error.to_string();
+                            checkpoint(&job_dir_clone, |job_dir| {
+                                job_dir.record_failed(&request, &error)
+                            });
+                            return TaskOutput::default();
+                        };
+
+                        warn!("{}, retrying in {:?}", scrape_error, delay);
+                        stats_clone.inc_value("retry/count");
+
+                        let retry_times = (retry_times + 1).to_string();
+                        let retry = request
+                            .with_meta(RETRY_TIMES_META.to_string(), retry_times)
+                            .with_dont_filter(true);
+                        TaskOutput { requests: Vec::new(), retry: Some((retry, delay)) }
+                    }
+                }
+            });
+
+            handles.push(handle);
+        }
+
+        let wake = scheduler
+            .next_due()
+            .map(|due| due.min(Instant::now() + SHUTDOWN_POLL_INTERVAL));
+        if handles.is_empty() {
+            if let Some(wake) = wake {
+                sleep_until(wake).await;
+            }
+            continue;
+        }
+
+        let mut finished = futures::future::select_all(handles);
+        let due = async move {
+            match wake {
+                Some(wake) => sleep_until(wake).await,
+                None => std::future::pending().await,
+            }
+        };
+        let result = tokio::select! {
+            (result, _, remaining) = &mut finished => {
+                handles = remaining;
+                result
+            }
+            _ = due => {
+                handles = finished.into_inner();
+                continue;
+            }
+        };
+
+        if let Ok(output) = result {
+            scheduler.enqueue_all(output.requests);
+            if let Some((request, delay)) = output.retry {
+                scheduler.enqueue_delayed(request, delay);
+            }
+        }
+
+        if job_dir.is_some() {
+            let output_files = pipeline.output_files().await;
+            checkpoint(&job_dir, |job_dir| job_dir.record_output_files(output_files));
+        }
+    }
+
+    shutdown_listener.abort();
+
+    if let Some(pool) = spider.get_http_client().proxy_pool() {
+        for report in pool.report() {
+            stats.set_value(&format!("proxy/{}/request_count", report.label), report.requests);
+            stats.set_value(&format!("proxy/{}/failure_count", report.label), report.failures);
+            stats.set_value(&format!("proxy/{}/ban_count", report.label), report.bans);
+        }
+    }
+
+    if let Some(hits) = spider.selector_hits() {
+        stats.record_selector_hits(&hits);
+    }
+    let coverage_check = coverage.finish(spider.name(), spider.get_config(), &stats);
+    let close_check = pipeline.close_spider(spider.name(), &stats).await;
+
+    stats.log_summary(spider.name());
+
+    match &job_dir {
+        Some(job_dir) if shutdown.load(Ordering::SeqCst) => {
+            warn!("Spider {} stopped, resume with --resume {}", spider.name(), job_dir.path().display());
+        }
+        None if shutdown.load(Ordering::SeqCst) => {
+            warn!("Spider {} stopped, rerun with --job-dir to make crawls resumable", spider.name());
+        }
+        _ => {
+            info!("Spider {} completed", spider.name());
+        }
+    }
+    close_check.and(coverage_check)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use serde_json::{ json, Value };
+    use crate::config::Config;
+    use async_trait::async_trait;
+    use crate::utils::HttpClient;
+    use crate::pipeline::ItemPipeline;
+    use crate::utils::test_server::TestServer;
+
+    #[derive(Clone)]
+    struct TestSpider {
+        config: Arc<Config>,
+        http_client: HttpClient,
+        base_url: String,
+    }
+
+    #[async_trait]
+    impl Spider for TestSpider {
+        type Item = Value;
+
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn get_config(&self) -> &Arc<Config> {
+            &self.config
+        }
+
+        fn get_http_client(&self) -> &HttpClient {
+            &self.http_client
+        }
+
+        async fn start_requests(&self) -> Vec<Request> {
+            ["ok", "server-error", "unparseable"]
+                .iter()
+                .map(|path| Request::new(format!("{}/{path}", self.base_url)))
+                .collect()
+        }
+
+        async fn parse(&self, _: String, request: &Request) -> Result<(Vec<Value>, Vec<Request>)> {
+            if request.url.ends_with("/unparseable") {
+                bail!("unexpected page");
+            }
+            Ok((vec![json!({ "url": request.url })], Vec::new()))
+        }
+    }
+
+    async fn crawl(base_url: &str, job_dir: &std::path::Path, resume: bool) {
+        let config = Arc::new(Config {
+            max_retries: 0,
+            job_dir: Some(job_dir.display().to_string()),
+            resume,
+            ..Config::default()
+        });
+        let http_client = HttpClient::new(config.clone()).unwrap();
+        let spider = TestSpider { config, http_client, base_url: base_url.to_string() };
+        run_spider(spider, Arc::new(ItemPipelineChain::new())).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn resume_does_not_refetch_failed_requests() {
+        let server = TestServer::start(|request_line| match request_line {
+            line if line.contains("/server-error") => (500, "error".to_string()),
+            _ => (200, "<html><body>page</body></html>".to_string()),
+        }).await;
+        let dir_name = format!("in-scraper-resume-{}", std::process::id());
+        let job_dir = std::env::temp_dir().join(dir_name);
+        let _ = std::fs::remove_dir_all(&job_dir);
+
+        crawl(&server.url, &job_dir, false).await;
+        assert_eq!(server.requests().len(), 3);
+        crawl(&server.url, &job_dir, true).await;
+
+        let failed = std::fs::read_to_string(job_dir.join("failed.jsonl")).unwrap();
+        std::fs::remove_dir_all(&job_dir).unwrap();
+        assert_eq!(server.requests().len(), 3);
+        assert_eq!(failed.lines().count(), 2);
+        assert!(failed.contains("/server-error") && failed.contains("unexpected page"));
+    }
+
+    struct Collect(Arc<std::sync::Mutex<Vec<Value>>>);
+
+    #[async_trait]
+    impl ItemPipeline for Collect {
+        async fn process_item(&self, item: Value, _: &str) -> Result<Option<Value>> {
+            self.0.lock().unwrap().push(item.clone());
+            Ok(Some(item))
+        }
+    }
+
+    #[tokio::test]
+    async fn item_response_ids_resolve_to_archived_records() {
+        let page = "<html><body>page</body></html>";
+        let server = TestServer::start(move |_| (200, page.to_string())).await;
+        let dir_name = format!("in-scraper-engine-archive-{}", std::process::id());
+        let archive_dir = std::env::temp_dir().join(dir_name);
+        let _ = std::fs::remove_dir_all(&archive_dir);
+        let config = Arc::new(Config {
+            archive_dir: Some(archive_dir.display().to_string()),
+            ..Config::default()
+        });
+        let http_client = HttpClient::new(config.clone()).unwrap();
+        let spider = TestSpider { config, http_client, base_url: server.url.clone() };
+        let items = Arc::default();
+        let mut pipeline = ItemPipelineChain::new();
+        pipeline.push(Collect(Arc::clone(&items)));
+        run_spider(spider, Arc::new(pipeline)).await.unwrap();
+
+        let items = items.lock().unwrap();
+        let records: Vec<_> = items
+            .iter()
+            .map(|item| {
+                let body_file = format!("{}.html", item["response_id"].as_str().unwrap());
+                ResponseArchive::load_record(&archive_dir.join("test").join(body_file))
+            })
+            .collect();
+        std::fs::remove_dir_all(&archive_dir).unwrap();
+
+        assert_eq!(items.len(), 2);
+        for (item, record) in items.iter().zip(records) {
+            let record = record.expect("response_id should resolve to an archive record");
+            assert_eq!(record.id, item["response_id"]);
+            assert_eq!(record.request.url, item["url"]);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use chrono::Utc;
+use crate::engine::{ FieldCoverage, Stats };
+use crate::engine::{ ArchivedItem, ResponseArchive };
+use crate::spiders::Spider;
+use crate::error::ScrapeError;
+use std::collections::HashMap;
+use tracing::{ info, error };
+use std::path::{ Path, PathBuf };
+use crate::pipeline::ItemPipelineChain;
+use anyhow::{ bail, Context, Result };
+
+const HTML_EXTENSIONS: [&str; 2] = ["html", "htm"];
+
+fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = fs
+                ::read_dir(path)
+                .with_context(|| format!("Failed to read directory {}", path.display()))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|entry| entry.is_file() && is_html(entry))
+                .collect();
+            entries.sort();
+            files.extend(entries);
+        } else if path.is_file() {
+            files.push(path.clone());
+        } else {
+            bail!("No such file or directory: {}", path.display());
+        }
+    }
+
+    if files.is_empty() {
+        bail!("No HTML files found");
+    }
+    Ok(files)
+}
+
+fn is_html(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| HTML_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+pub async fn parse_files<S: Spider>(
+    spider: &S,
+    paths: &[PathBuf],
+    meta: &HashMap<String, String>,
+    pipeline: &ItemPipelineChain
+) -> Result<()> {
+    let stats = Stats::new();
+    let coverage = FieldCoverage::new();
+    let files = collect_files(paths)?;
+
+    let archived = files.iter().any(|file| ResponseArchive::load_record(file).is_some());
+    pipeline.open_spider(spider.name(), &ArchivedItem::<S::Item>::schema(archived)).await?;
+
+    for file in &files {
+        let path = fs::canonicalize(file).unwrap_or_else(|_| file.clone());
+        let body = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let body = String::from_utf8_lossy(&body).to_string();
+
+        let record = ResponseArchive::load_record(&path);
+        let crawled_at = record.as_ref().map_or_else(Utc::now, |record| record.archived_at);
+        let mut request = match &record {
+            Some(record) => record.request.clone(),
+            None => spider.synthetic_request(&path),
+        };
+        request.meta.extend(meta.clone());
+        stats.inc_value("parse/file_count");
+
+        let (items, requests) = match spider.parse(body, &request).await {
+            Ok(output) => output,
+            Err(e) => {
+                error!("Failed to parse {}: {:#}", path.display(), e);
+                stats.inc_value("parse/error_count");
+                if let Some(ScrapeError::EmptyPage { .. }) = e.downcast_ref() {
+                    coverage.observe_empty_page();
+                }
+                continue;
+            }
+        };
+
+        info!("Parsed {}: {} items", path.display(), items.len());
+        if !requests.is_empty() {
+            stats.inc_value_by("parse/ignored_request_count", requests.len() as u64);
+        }
+
+        for item in items {
+            coverage.observe(&item);
+            let response_id = record.as_ref().map(|record| record.id.clone());
+            let item = ArchivedItem::to_value(item, response_id, crawled_at)?;
+            match pipeline.process_item(item, spider.name()).await? {
+                Some(_) => stats.inc_value("item_scraped_count"),
+                None => stats.inc_value("item_dropped_count"),
+            }
+        }
+    }
+
+    if let Some(hits) = spider.selector_hits() {
+        stats.record_selector_hits(&hits);
+    }
+    let coverage_check = coverage.finish(spider.name(), spider.get_config(), &stats);
+    let close_check = pipeline.close_spider(spider.name(), &stats).await;
+    stats.log_summary(spider.name());
+
+    let failed = stats.get_value("parse/error_count");
+    if failed > 0 {
+        bail!("{} of {} files failed to parse", failed, files.len());
+    }
+    close_check.and(coverage_check)
+}
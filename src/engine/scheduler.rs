@@ -0,0 +1,116 @@
+use tracing::debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use crate::engine::Stats;
+use crate::spiders::Request;
+use std::collections::HashSet;
+
+pub struct Scheduler {
+    queue: Vec<Request>,
+    delayed: Vec<(Instant, Request)>,
+    seen: HashSet<String>,
+    meta_keys: Vec<String>,
+    stats: Arc<Stats>,
+}
+
+impl Scheduler {
+    pub fn new(meta_keys: Vec<String>, stats: Arc<Stats>) -> Self {
+        Self {
+            queue: Vec::new(),
+            delayed: Vec::new(),
+            seen: HashSet::new(),
+            meta_keys,
+            stats,
+        }
+    }
+
+    pub fn mark_seen(&mut self, fingerprints: impl IntoIterator<Item = String>) {
+        self.seen.extend(fingerprints);
+    }
+
+    pub fn enqueue(&mut self, request: Request) -> bool {
+        let fingerprint = request.fingerprint(&self.meta_keys);
+
+        if !self.seen.insert(fingerprint) && !request.dont_filter {
+            debug!("Filtered duplicate request: {}", request.url);
+            self.stats.inc_value("dupefilter/filtered");
+            return false;
+        }
+
+        self.stats.inc_value("scheduler/enqueued");
+        self.queue.push(request);
+        true
+    }
+
+    pub fn enqueue_all(&mut self, requests: impl IntoIterator<Item = Request>) {
+        for request in requests {
+            self.enqueue(request);
+        }
+    }
+
+    pub fn enqueue_delayed(&mut self, request: Request, delay: Duration) {
+        self.stats.inc_value("scheduler/enqueued_delayed");
+        self.delayed.push((Instant::now() + delay, request));
+    }
+
+    pub fn next_request(&mut self) -> Option<Request> {
+        let now = Instant::now();
+        let (due, delayed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.delayed)
+            .into_iter()
+            .partition(|(not_before, _)| *not_before <= now);
+        self.delayed = delayed;
+        self.queue.extend(due.into_iter().map(|(_, request)| request));
+        self.queue.pop()
+    }
+
+    pub fn next_due(&self) -> Option<Instant> {
+        self.delayed.iter().map(|(not_before, _)| *not_before).min()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len() + self.delayed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty() && self.delayed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler() -> Scheduler {
+        Scheduler::new(Vec::new(), Arc::new(Stats::new()))
+    }
+
+    fn request(url: &str) -> Request {
+        Request::new(url.to_string())
+    }
+
+    #[test]
+    fn filters_duplicates_unless_dont_filter() {
+        let mut scheduler = scheduler();
+        assert!(scheduler.enqueue(request("https://example.com/a?x=1&y=2")));
+        assert!(!scheduler.enqueue(request("https://example.com/a?y=2&x=1")));
+        assert!(scheduler.enqueue(request("https://example.com/a?x=1&y=2").with_dont_filter(true)));
+        assert_eq!(scheduler.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn delayed_requests_wait_for_their_due_time() {
+        let mut scheduler = scheduler();
+        scheduler.enqueue_delayed(request("https://example.com/retry"), Duration::from_millis(50));
+        scheduler.enqueue(request("https://example.com/ready"));
+
+        let due = scheduler.next_due().unwrap();
+        assert_eq!(scheduler.next_request().unwrap().url, "https://example.com/ready");
+        assert!(scheduler.next_request().is_none());
+        assert!(!scheduler.is_empty());
+
+        tokio::time::sleep_until(due).await;
+        assert_eq!(scheduler.next_request().unwrap().url, "https://example.com/retry");
+        assert!(scheduler.is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Mutex;
+use tracing::{ info, warn };
+use std::collections::BTreeMap;
+use crate::utils::selector_utils::SelectorHits;
+
+#[derive(Default)]
+pub struct Stats {
+    values: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inc_value(&self, key: &str) {
+        self.inc_value_by(key, 1);
+    }
+
+    pub fn inc_value_by(&self, key: &str, count: u64) {
+        let mut values = self.values.lock().unwrap();
+        *values.entry(key.to_string()).or_insert(0) += count;
+    }
+
+    pub fn set_value(&self, key: &str, value: u64) {
+        self.values.lock().unwrap().insert(key.to_string(), value);
+    }
+
+    pub fn get_value(&self, key: &str) -> u64 {
+        self.values.lock().unwrap().get(key).copied().unwrap_or(0)
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, u64> {
+        self.values.lock().unwrap().clone()
+    }
+
+    pub fn record_selector_hits(&self, hits: &SelectorHits) {
+        for (key, count) in hits.report() {
+            self.set_value(&format!("selector/{key}"), count);
+        }
+
+        for (field, count) in hits.fallback_fields() {
+            warn!("Primary selector for {} stopped matching, fell back {} times", field, count);
+        }
+    }
+
+    pub fn log_summary(&self, spider_name: &str) {
+        info!("Stats for spider {}:", spider_name);
+        for (key, value) in self.snapshot() {
+            info!("  {}: {}", key, value);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use url::Url;
+use tracing::debug;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use crate::config::Config;
+use std::collections::HashMap;
+use tokio::time::{ sleep, Instant };
+
+const TOO_MANY_REQUESTS: u16 = 429;
+const BLOCKED_STATUS: u16 = 999;
+
+struct HostSlot {
+    delay: Duration,
+    next_slot: Instant,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+pub struct Throttle {
+    download_delay: Duration,
+    randomize_delay: bool,
+    rate_limit: Option<f64>,
+    burst: f64,
+    autothrottle: bool,
+    autothrottle_max_delay: Duration,
+    autothrottle_target_concurrency: f64,
+    start_delay: Duration,
+    hosts: Mutex<HashMap<String, HostSlot>>,
+}
+
+impl Throttle {
+    pub fn new(config: &Config) -> Self {
+        let download_delay = Duration::from_millis(config.download_delay_ms);
+        let start_delay = if config.autothrottle_enabled {
+            download_delay.max(Duration::from_millis(config.autothrottle_start_delay_ms))
+        } else {
+            download_delay
+        };
+
+        if config.offline {
+            return Self::unthrottled(config);
+        }
+
+        Self {
+            download_delay,
+            randomize_delay: config.randomize_download_delay,
+            rate_limit: config.rate_limit.filter(|rate| *rate > 0.0),
+            burst: f64::from(config.rate_limit_burst.max(1)),
+            autothrottle: config.autothrottle_enabled,
+            autothrottle_max_delay: Duration::from_millis(config.autothrottle_max_delay_ms),
+            autothrottle_target_concurrency: config.autothrottle_target_concurrency.max(0.1),
+            start_delay,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn unthrottled(config: &Config) -> Self {
+        Self {
+            download_delay: Duration::ZERO,
+            randomize_delay: false,
+            rate_limit: None,
+            burst: 1.0,
+            autothrottle: false,
+            autothrottle_max_delay: Duration::from_millis(config.autothrottle_max_delay_ms),
+            autothrottle_target_concurrency: 1.0,
+            start_delay: Duration::ZERO,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn host_key(url: &str) -> String {
+        Url::parse(url)
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    fn jittered(&self, delay: Duration) -> Duration {
+        if self.randomize_delay && !delay.is_zero() {
+            delay.mul_f64(0.5 + fastrand::f64())
+        } else {
+            delay
+        }
+    }
+
+    pub async fn acquire(&self, url: &str) -> Duration {
+        let now = Instant::now();
+        let ready_at = {
+            let mut hosts = self.hosts.lock().await;
+            let slot = hosts.entry(Self::host_key(url)).or_insert_with(|| HostSlot {
+                delay: self.start_delay,
+                next_slot: now,
+                tokens: self.burst,
+                last_refill: now,
+            });
+
+            let mut ready_at = slot.next_slot.max(now);
+
+            if let Some(rate) = self.rate_limit {
+                let elapsed = now.duration_since(slot.last_refill).as_secs_f64();
+                slot.tokens = (slot.tokens + elapsed * rate).min(self.burst) - 1.0;
+                slot.last_refill = now;
+                if slot.tokens < 0.0 {
+                    ready_at = ready_at.max(now + Duration::from_secs_f64(-slot.tokens / rate));
+                }
+            }
+
+            slot.next_slot = ready_at + self.jittered(slot.delay);
+            ready_at
+        };
+
+        let wait = ready_at.saturating_duration_since(now);
+        if !wait.is_zero() {
+            debug!("Throttling {} for {:?}", url, wait);
+            sleep(wait).await;
+        }
+        wait
+    }
+
+    pub async fn record_response(&self, url: &str, latency: Duration, status: Option<u16>) {
+        if !self.autothrottle {
+            return;
+        }
+
+        let mut hosts = self.hosts.lock().await;
+        let Some(slot) = hosts.get_mut(&Self::host_key(url)) else {
+            return;
+        };
+
+        let banned = matches!(status, Some(TOO_MANY_REQUESTS) | Some(BLOCKED_STATUS));
+        let new_delay = if banned {
+            (slot.delay * 2).max(self.start_delay)
+        } else {
+            let target = latency.div_f64(self.autothrottle_target_concurrency);
+            let averaged = (slot.delay + target) / 2;
+            let new_delay = averaged.max(target);
+
+            if status.is_some_and(|status| status >= 400) && new_delay < slot.delay {
+                slot.delay
+            } else {
+                new_delay
+            }
+        };
+
+        let new_delay = new_delay.min(self.autothrottle_max_delay).max(self.download_delay);
+        if new_delay != slot.delay {
+            debug!("AutoThrottle delay for {} adjusted to {:?}", url, new_delay);
+        }
+        slot.delay = new_delay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST: &str = "https://www.linkedin.com/in/someone";
+
+    fn throttle(config: Config) -> Throttle {
+        Throttle::new(&config)
+    }
+
+    async fn delay(throttle: &Throttle) -> Duration {
+        throttle.hosts.lock().await[&Throttle::host_key(HOST)].delay
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_the_download_delay_per_host() {
+        let throttle = throttle(Config {
+            download_delay_ms: 1000,
+            randomize_download_delay: false,
+            ..Config::default()
+        });
+
+        let start = Instant::now();
+        assert_eq!(throttle.acquire(HOST).await, Duration::ZERO);
+        assert_eq!(throttle.acquire(HOST).await, Duration::from_secs(1));
+        assert_eq!(throttle.acquire("https://example.com/").await, Duration::ZERO);
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(throttle.acquire(HOST).await, Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn jitters_the_delay_between_half_and_one_and_a_half_times() {
+        let throttle = throttle(Config { download_delay_ms: 1000, ..Config::default() });
+        throttle.acquire(HOST).await;
+
+        let waits: Vec<Duration> = futures::future::join_all(
+            (0..50).map(|_| async { throttle.acquire(HOST).await })
+        ).await;
+        let mut previous = Duration::ZERO;
+        for wait in waits {
+            let jitter = wait - previous;
+            assert!(jitter >= Duration::from_millis(500), "{jitter:?}");
+            assert!(jitter < Duration::from_millis(1500), "{jitter:?}");
+            previous = wait;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn token_bucket_allows_a_burst_then_refills_at_the_rate() {
+        let throttle = throttle(Config {
+            rate_limit: Some(2.0),
+            rate_limit_burst: 3,
+            ..Config::default()
+        });
+
+        for _ in 0..3 {
+            assert_eq!(throttle.acquire(HOST).await, Duration::ZERO);
+        }
+        assert_eq!(throttle.acquire(HOST).await, Duration::from_millis(500));
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        for _ in 0..3 {
+            assert_eq!(throttle.acquire(HOST).await, Duration::ZERO);
+        }
+        assert_eq!(throttle.acquire(HOST).await, Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn autothrottle_backs_off_on_bans_and_converges_to_the_latency_target() {
+        let throttle = throttle(Config {
+            autothrottle_enabled: true,
+            autothrottle_start_delay_ms: 1000,
+            autothrottle_max_delay_ms: 10_000,
+            ..Config::default()
+        });
+        throttle.acquire(HOST).await;
+        assert_eq!(delay(&throttle).await, Duration::from_secs(1));
+
+        throttle.record_response(HOST, Duration::from_millis(100), Some(429)).await;
+        assert_eq!(delay(&throttle).await, Duration::from_secs(2));
+        throttle.record_response(HOST, Duration::from_millis(100), Some(999)).await;
+        assert_eq!(delay(&throttle).await, Duration::from_secs(4));
+        for _ in 0..5 {
+            throttle.record_response(HOST, Duration::from_millis(100), Some(999)).await;
+        }
+        assert_eq!(delay(&throttle).await, Duration::from_secs(10));
+
+        throttle.record_response(HOST, Duration::from_millis(200), Some(500)).await;
+        assert_eq!(delay(&throttle).await, Duration::from_secs(10));
+
+        for _ in 0..20 {
+            throttle.record_response(HOST, Duration::from_millis(200), Some(200)).await;
+        }
+        let converged = delay(&throttle).await;
+        assert!(converged >= Duration::from_millis(200), "{converged:?}");
+        assert!(converged < Duration::from_millis(210), "{converged:?}");
+    }
+}
@@ -0,0 +1,64 @@
+use thiserror::Error;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Error)]
+pub enum ScrapeError {
+    #[error("Auth wall served for {url}")]
+    AuthWall { url: String },
+
+    #[error("Security challenge served for {url}")]
+    Challenge { url: String },
+
+    #[error("Request to {url} blocked with status {status}")]
+    Blocked { url: String, status: u16 },
+
+    #[error("HTTP error {status} for {url}")]
+    HttpStatus { url: String, status: u16, retry_after: Option<Duration> },
+
+    #[error("Empty page served for {url}")]
+    EmptyPage { url: String },
+
+    #[error(
+        "LinkedIn session expired or invalid: {url} redirected to {redirected_to}, refresh the li_at/JSESSIONID cookies"
+    )]
+    SessionExpired { url: String, redirected_to: String },
+
+    #[error("No cached response for {url} in offline mode")]
+    CacheMiss { url: String },
+}
+
+impl ScrapeError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::AuthWall { .. } => "auth_wall",
+            Self::Challenge { .. } => "challenge",
+            Self::Blocked { .. } => "blocked",
+            Self::HttpStatus { .. } => "http_status",
+            Self::EmptyPage { .. } => "empty_page",
+            Self::SessionExpired { .. } => "session_expired",
+            Self::CacheMiss { .. } => "cache_miss",
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::HttpStatus { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::AuthWall { .. })
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Blocked { status, .. } | Self::HttpStatus { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Self::SessionExpired { .. })
+    }
+}
@@ -1,30 +1,55 @@
+use schemars::{ schema_for, JsonSchema, Schema };
 use serde::{ Deserialize, Serialize };
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub const SCHEMA_VERSION: u32 = 3;
+
+const MONTH_YEAR_PATTERN: &str = r"^(?:\p{L}+\.? )?\d{4}$|^\p{L}+$";
+const POSTING_TIME_PATTERN: &str = concat!(
+    r"(?i)^(?:reposted )?(?:just now|today|yesterday|\d{4}-\d{2}-\d{2}",
+    r"|\d+\+? (?:second|minute|hour|day|week|month|year)s? ago)$"
+);
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CompanyProfile {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    #[schemars(url, extend("x-key" = true))]
+    pub url: String,
+    #[schemars(length(min = 1))]
     pub name: String,
-    pub summary: String,
+    pub summary: Option<String>,
     pub industry: Option<String>,
     pub size: Option<String>,
     pub founded: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct JobListing {
-    pub job_title: String,
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    pub job_title: Option<String>,
+    #[schemars(url, extend("x-key" = true))]
     pub job_detail_url: String,
-    pub job_listed: String,
-    pub company_name: String,
-    pub company_link: String,
-    pub company_location: String,
+    #[schemars(regex(pattern = POSTING_TIME_PATTERN))]
+    pub job_listed: Option<String>,
+    pub job_listed_date: Option<String>,
+    pub company_name: Option<String>,
+    #[schemars(url)]
+    pub company_link: Option<String>,
+    pub company_location: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct PersonProfile {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    #[schemars(length(min = 1), extend("x-key" = true))]
     pub profile: String,
+    #[schemars(url)]
     pub url: String,
+    #[schemars(length(min = 1))]
     pub name: String,
-    pub description: String,
+    pub description: Option<String>,
     pub location: Option<String>,
     pub followers: Option<String>,
     pub connections: Option<String>,
@@ -35,43 +60,60 @@ pub struct PersonProfile {
     pub activities: Vec<Activity>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct Experience {
     pub organization_profile: Option<String>,
     pub location: Option<String>,
     pub description: Option<String>,
     pub duration: Option<String>,
+    pub duration_months: Option<u32>,
+    #[schemars(regex(pattern = MONTH_YEAR_PATTERN))]
     pub start_time: Option<String>,
+    #[schemars(regex(pattern = MONTH_YEAR_PATTERN))]
     pub end_time: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
     pub logo: Option<String>,
     pub title: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct Education {
-    pub organization: String,
+    pub organization: Option<String>,
     pub organization_profile: Option<String>,
     pub course_details: Option<String>,
     pub description: Option<String>,
+    #[schemars(regex(pattern = MONTH_YEAR_PATTERN))]
     pub start_time: Option<String>,
+    #[schemars(regex(pattern = MONTH_YEAR_PATTERN))]
     pub end_time: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct Project {
     pub name: Option<String>,
     pub description: Option<String>,
     pub url: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct Language {
     pub name: Option<String>,
     pub proficiency: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct Activity {
     pub title: Option<String>,
     pub url: Option<String>,
 }
+
+pub fn json_schemas() -> Vec<(&'static str, Schema)> {
+    vec![
+        ("company-profile", schema_for!(CompanyProfile)),
+        ("job-listing", schema_for!(JobListing)),
+        ("person-profile", schema_for!(PersonProfile))
+    ]
+}
@@ -1,9 +1,13 @@
+pub mod error;
 pub mod config;
 pub mod items;
 pub mod utils;
+pub mod shell;
+pub mod engine;
 pub mod spiders;
 pub mod pipeline;
 pub mod selectors;
 pub mod middleware;
 
 pub use config::Config;
+pub use error::ScrapeError;
@@ -1,39 +1,305 @@
-use anyhow::Result;
 use std::sync::Arc;
-use tokio::sync::Semaphore;
-use tracing::{ info, error };
-use clap::{ Parser, Subcommand };
+use std::path::PathBuf;
+use std::collections::HashMap;
+use anyhow::{ Context, Result };
+use clap::{ Args, Parser, Subcommand };
 use in_scraper::{
     config::Config,
-    pipeline::JsonPipeline,
-    spiders::{ CompanyProfileSpider, JobsSpider, PeopleProfileSpider, Spider },
+    engine::{ parse_files, run_spider },
+    shell::Shell,
+    items::json_schemas,
+    selectors::SelectorStore,
+    pipeline::ItemPipelineChain,
+    spiders::{ CompanyProfileSpider, JobsSpider, PeopleProfileSpider },
 };
 
 #[derive(Parser)]
 #[command(name = "in-scraper")]
 #[command(about = "LinkedIn data scraper", long_about = None)]
 struct Cli {
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Args)]
+struct CommonArgs {
+    #[arg(short, long)]
+    concurrent: Option<usize>,
+
+    #[arg(short, long)]
+    output: Option<String>,
+
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    #[arg(long)]
+    retries: Option<u32>,
+
+    #[arg(long, conflicts_with = "resume")]
+    job_dir: Option<String>,
+
+    #[arg(long, value_name = "JOBDIR")]
+    resume: Option<String>,
+
+    #[arg(long, value_delimiter = ',')]
+    spider_middlewares: Option<Vec<String>>,
+
+    #[arg(long, value_delimiter = ',')]
+    downloader_middlewares: Option<Vec<String>>,
+
+    #[arg(long)]
+    cookie_file: Option<String>,
+
+    #[arg(long, value_name = "MS")]
+    download_delay: Option<u64>,
+
+    #[arg(long, value_name = "REQUESTS_PER_SECOND")]
+    rate_limit: Option<f64>,
+
+    #[arg(long)]
+    autothrottle: bool,
+
+    #[arg(long)]
+    proxy_file: Option<String>,
+
+    #[arg(long, value_parser = ["per_request", "sticky"])]
+    proxy_rotation: Option<String>,
+
+    #[arg(long, value_name = "FILE")]
+    header_profiles: Option<String>,
+
+    #[arg(long, value_parser = ["per_request", "sticky"])]
+    header_profile_rotation: Option<String>,
+
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<String>,
+
+    #[arg(long, value_name = "SECONDS")]
+    cache_expiration: Option<u64>,
+
+    #[arg(long)]
+    cache_gzip: bool,
+
+    #[arg(long)]
+    offline: bool,
+
+    #[arg(long, value_name = "DIR")]
+    archive_responses: Option<String>,
+
+    #[arg(long, value_name = "PATH")]
+    selectors: Option<String>,
+
+    #[arg(long)]
+    watch_selectors: bool,
+
+    #[command(flatten)]
+    output_format: OutputArgs,
+
+    #[command(flatten)]
+    coverage: CoverageArgs,
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    #[arg(long, value_parser = ["jsonl", "csv", "tsv", "sqlite", "stdout"])]
+    format: Option<String>,
+
+    #[arg(long, value_delimiter = ',')]
+    item_pipelines: Option<Vec<String>>,
+
+    #[arg(long, value_parser = ["wide", "tables"])]
+    csv_layout: Option<String>,
+
+    #[arg(long, value_name = "N")]
+    csv_max_entries: Option<usize>,
+
+    #[arg(long, value_name = "PATH")]
+    sqlite_path: Option<String>,
+}
+
+impl OutputArgs {
+    fn apply_parse(&self, config: &mut Config, output: Option<&String>) {
+        self.apply(config);
+        match output {
+            Some(output) => {
+                config.output_dir = output.clone();
+            }
+            None if self.format.is_none() => {
+                config.output_format = "stdout".to_string();
+            }
+            None => {}
+        }
+    }
+
+    fn apply(&self, config: &mut Config) {
+        if let Some(format) = &self.format {
+            config.output_format = format.clone();
+        }
+
+        if let Some(item_pipelines) = &self.item_pipelines {
+            config.item_pipelines = item_pipelines.clone();
+        }
+
+        if let Some(csv_layout) = &self.csv_layout {
+            config.csv_layout = csv_layout.clone();
+        }
+
+        if let Some(csv_max_entries) = self.csv_max_entries {
+            config.csv_max_entries = csv_max_entries;
+        }
+
+        if let Some(sqlite_path) = &self.sqlite_path {
+            config.sqlite_path = Some(sqlite_path.clone());
+        }
+    }
+}
+
+#[derive(Args)]
+struct CoverageArgs {
+    #[arg(long, value_name = "PATH")]
+    coverage_baseline: Option<String>,
+
+    #[arg(long, requires = "coverage_baseline")]
+    update_coverage_baseline: bool,
+
+    #[arg(long, value_name = "PERCENT")]
+    coverage_warn_drop: Option<f64>,
+
+    #[arg(long, value_name = "PERCENT")]
+    coverage_fail_drop: Option<f64>,
+}
+
+impl CoverageArgs {
+    fn apply(&self, config: &mut Config) {
+        if let Some(baseline) = &self.coverage_baseline {
+            config.coverage_baseline = Some(baseline.clone());
+        }
+
+        if self.update_coverage_baseline {
+            config.update_coverage_baseline = true;
+        }
+
+        if let Some(warn_drop) = self.coverage_warn_drop {
+            config.coverage_warn_drop = warn_drop;
+        }
+
+        if let Some(fail_drop) = self.coverage_fail_drop {
+            config.coverage_fail_drop = Some(fail_drop);
+        }
+    }
+}
+
+impl CommonArgs {
+    fn apply(&self, config: &mut Config) {
+        if let Some(concurrent) = self.concurrent {
+            config.concurrent_requests = concurrent;
+        }
+
+        if let Some(output) = &self.output {
+            config.output_dir = output.clone();
+        }
+
+        if let Some(timeout) = self.timeout {
+            config.request_timeout = timeout;
+        }
+
+        if let Some(retries) = self.retries {
+            config.max_retries = retries;
+        }
+
+        if let Some(job_dir) = &self.job_dir {
+            config.job_dir = Some(job_dir.clone());
+        }
+
+        if let Some(resume) = &self.resume {
+            config.job_dir = Some(resume.clone());
+            config.resume = true;
+        }
+
+        if let Some(spider_middlewares) = &self.spider_middlewares {
+            config.spider_middlewares = spider_middlewares.clone();
+        }
+
+        if let Some(downloader_middlewares) = &self.downloader_middlewares {
+            config.downloader_middlewares = downloader_middlewares.clone();
+        }
+
+        if let Some(cookie_file) = &self.cookie_file {
+            config.cookie_file = Some(cookie_file.clone());
+        }
+
+        if let Some(download_delay) = self.download_delay {
+            config.download_delay_ms = download_delay;
+        }
+
+        if let Some(rate_limit) = self.rate_limit {
+            config.rate_limit = Some(rate_limit);
+        }
+
+        if self.autothrottle {
+            config.autothrottle_enabled = true;
+        }
+
+        if let Some(proxy_file) = &self.proxy_file {
+            config.proxy_file = Some(proxy_file.clone());
+        }
+
+        if let Some(proxy_rotation) = &self.proxy_rotation {
+            config.proxy_rotation = proxy_rotation.clone();
+        }
+
+        if let Some(header_profiles) = &self.header_profiles {
+            config.header_profiles_file = Some(header_profiles.clone());
+        }
+
+        if let Some(rotation) = &self.header_profile_rotation {
+            config.header_profile_rotation = rotation.clone();
+        }
+
+        if let Some(cache_dir) = &self.cache_dir {
+            config.http_cache_dir = Some(cache_dir.clone());
+        }
+
+        if let Some(cache_expiration) = self.cache_expiration {
+            config.http_cache_expiration_secs = cache_expiration;
+        }
+
+        if self.cache_gzip {
+            config.http_cache_gzip = true;
+        }
+
+        if self.offline {
+            config.offline = true;
+        }
+
+        if let Some(archive_responses) = &self.archive_responses {
+            config.archive_dir = Some(archive_responses.clone());
+        }
+
+        if let Some(selectors) = &self.selectors {
+            config.selectors_file = Some(selectors.clone());
+        }
+
+        if self.watch_selectors {
+            config.watch_selectors = true;
+        }
+
+        self.output_format.apply(config);
+        self.coverage.apply(config);
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     CompanyProfile {
         #[arg(long)]
         urls: Vec<String>,
 
-        #[arg(short, long, default_value_t = 1)]
-        concurrent: usize,
-
-        #[arg(short, long, default_value = "data")]
-        output: String,
-
-        #[arg(long, default_value_t = 30)]
-        timeout: u64,
-
-        #[arg(long, default_value_t = 3)]
-        retries: u32,
+        #[command(flatten)]
+        common: CommonArgs,
     },
     Jobs {
         #[arg(long)]
@@ -41,133 +307,221 @@ enum Commands {
         #[arg(long)]
         location: String,
 
-        #[arg(short, long, default_value_t = 1)]
-        concurrent: usize,
-
-        #[arg(short, long, default_value = "data")]
-        output: String,
-
-        #[arg(long, default_value_t = 30)]
-        timeout: u64,
-
-        #[arg(long, default_value_t = 3)]
-        retries: u32,
+        #[command(flatten)]
+        common: CommonArgs,
     },
     PeopleProfile {
         #[arg(long)]
         profiles: Vec<String>,
 
-        #[arg(short, long, default_value_t = 1)]
-        concurrent: usize,
+        #[command(flatten)]
+        common: CommonArgs,
+    },
+    Parse {
+        #[arg(value_parser = ["company-profile", "jobs", "people-profile"])]
+        spider: String,
+
+        #[arg(required = true, value_name = "FILE_OR_DIR")]
+        paths: Vec<PathBuf>,
+
+        #[arg(long, value_name = "KEY=VALUE", value_parser = parse_meta)]
+        meta: Vec<(String, String)>,
+
+        #[arg(short, long)]
+        output: Option<String>,
+
+        #[arg(long, value_name = "PATH")]
+        selectors: Option<String>,
 
-        #[arg(short, long, default_value = "data")]
-        output: String,
+        #[command(flatten)]
+        output_format: OutputArgs,
 
-        #[arg(long, default_value_t = 30)]
-        timeout: u64,
+        #[command(flatten)]
+        coverage: CoverageArgs,
+    },
+    Shell {
+        #[arg(value_name = "URL_OR_FILE")]
+        target: Option<String>,
+
+        #[arg(long, value_parser = ["company-profile", "jobs", "people-profile"])]
+        spider: Option<String>,
 
-        #[arg(long, default_value_t = 3)]
-        retries: u32,
+        #[command(flatten)]
+        common: CommonArgs,
+    },
+    Schema {
+        #[arg(value_parser = ["company-profile", "job-listing", "person-profile"])]
+        item: Option<String>,
     },
 }
 
+impl Cli {
+    fn base_config(&self) -> Result<Config> {
+        Ok(match &self.config {
+            Some(path) => Config::load(path)?.with_env(),
+            None => Config::from_env(),
+        })
+    }
+}
+
+fn parse_meta(value: &str) -> Result<(String, String)> {
+    let (key, value) = value.split_once('=').context("Expected KEY=VALUE")?;
+    Ok((key.trim().to_string(), value.to_string()))
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
 
     let cli = Cli::parse();
-    let mut config = Config::from_env();
+    let mut config = cli.base_config()?;
 
     match &cli.command {
-        Commands::CompanyProfile { urls, concurrent, output, timeout, retries } => {
-            configure_common(&mut config, *concurrent, output, *timeout, *retries);
+        Commands::CompanyProfile { urls, common } => {
+            common.apply(&mut config);
             let config = Arc::new(config);
-            let pipeline = Arc::new(JsonPipeline::new(config.clone()));
-            let spider = CompanyProfileSpider::new(config.clone(), urls.clone());
+            let selectors = Arc::new(SelectorStore::from_config(&config)?);
+            let pipeline = Arc::new(ItemPipelineChain::from_config(config.clone())?);
+            let spider = CompanyProfileSpider::new(config.clone(), urls.clone()).with_selectors(selectors);
             run_spider(spider, pipeline).await?;
         }
-        Commands::Jobs { keywords, location, concurrent, output, timeout, retries } => {
-            configure_common(&mut config, *concurrent, output, *timeout, *retries);
+        Commands::Jobs { keywords, location, common } => {
+            common.apply(&mut config);
             let config = Arc::new(config);
-            let pipeline = Arc::new(JsonPipeline::new(config.clone()));
-            let spider = JobsSpider::new(config.clone(), keywords.clone(), location.clone());
+            let selectors = Arc::new(SelectorStore::from_config(&config)?);
+            let pipeline = Arc::new(ItemPipelineChain::from_config(config.clone())?);
+            let spider = JobsSpider::new(config.clone(), keywords.clone(), location.clone()).with_selectors(selectors);
             run_spider(spider, pipeline).await?;
         }
-        Commands::PeopleProfile { profiles, concurrent, output, timeout, retries } => {
-            configure_common(&mut config, *concurrent, output, *timeout, *retries);
+        Commands::PeopleProfile { profiles, common } => {
+            common.apply(&mut config);
             let config = Arc::new(config);
-            let pipeline = Arc::new(JsonPipeline::new(config.clone()));
-            let spider = PeopleProfileSpider::new(config.clone(), profiles.clone());
+            let selectors = Arc::new(SelectorStore::from_config(&config)?);
+            let pipeline = Arc::new(ItemPipelineChain::from_config(config.clone())?);
+            let spider = PeopleProfileSpider::new(config.clone(), profiles.clone()).with_selectors(selectors);
             run_spider(spider, pipeline).await?;
         }
+        Commands::Parse { spider, paths, meta, output, selectors, output_format, coverage } => {
+            output_format.apply_parse(&mut config, output.as_ref());
+            coverage.apply(&mut config);
+            if let Some(selectors) = selectors {
+                config.selectors_file = Some(selectors.clone());
+            }
+            let config = Arc::new(config);
+            let selectors = Arc::new(SelectorStore::from_config(&config)?);
+            let pipeline = ItemPipelineChain::from_config(config.clone())?;
+            let meta: HashMap<String, String> = meta.iter().cloned().collect();
+
+            match spider.as_str() {
+                "company-profile" => {
+                    let spider = CompanyProfileSpider::new(config.clone(), Vec::new());
+                    let spider = spider.with_selectors(selectors);
+                    parse_files(&spider, paths, &meta, &pipeline).await?;
+                }
+                "jobs" => {
+                    let spider = JobsSpider::new(config.clone(), String::new(), String::new());
+                    let spider = spider.with_selectors(selectors);
+                    parse_files(&spider, paths, &meta, &pipeline).await?;
+                }
+                _ => {
+                    let spider = PeopleProfileSpider::new(config.clone(), Vec::new());
+                    let spider = spider.with_selectors(selectors);
+                    parse_files(&spider, paths, &meta, &pipeline).await?;
+                }
+            }
+        }
+        Commands::Shell { target, spider, common } => {
+            common.apply(&mut config);
+            let mut shell = Shell::new(Arc::new(config), spider.clone())?;
+            if let Some(target) = target {
+                if let Err(e) = shell.load(target).await {
+                    println!("Error: {e:#}");
+                }
+            }
+            shell.run().await?;
+        }
+        Commands::Schema { item } => {
+            let schemas = json_schemas();
+            match item {
+                Some(item) => {
+                    let (_, schema) = schemas
+                        .iter()
+                        .find(|(name, _)| name == item)
+                        .context("Unknown item type")?;
+                    println!("{}", serde_json::to_string_pretty(schema)?);
+                }
+                None => {
+                    let schemas: serde_json::Map<String, serde_json::Value> = schemas
+                        .into_iter()
+                        .map(|(name, schema)| (name.to_string(), schema.to_value()))
+                        .collect();
+                    println!("{}", serde_json::to_string_pretty(&schemas)?);
+                }
+            }
+        }
     }
 
     Ok(())
 }
 
-fn configure_common(
-    config: &mut Config,
-    concurrent: usize,
-    output: &str,
-    timeout: u64,
-    retries: u32
-) {
-    config.concurrent_requests = concurrent;
-    config.output_dir = output.to_string();
-    config.request_timeout = timeout;
-    config.max_retries = retries;
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-async fn run_spider<S: Spider + 'static>(spider: S, pipeline: Arc<JsonPipeline>) -> Result<()> {
-    info!("Starting spider: {}", spider.name());
-
-    let semaphore = Arc::new(Semaphore::new(spider.get_config().concurrent_requests));
-    let mut request_queue = spider.start_requests().await;
-    let mut handles = vec![];
-
-    while !request_queue.is_empty() || !handles.is_empty() {
-        while let Some(request) = request_queue.pop() {
-            let spider_clone = spider.clone();
-            let pipeline_clone = pipeline.clone();
-            let semaphore_clone = semaphore.clone();
-
-            let handle = tokio::spawn(async move {
-                let _permit = semaphore_clone.acquire().await.unwrap();
-
-                match spider_clone.execute_request(request).await {
-                    Ok((items, next_requests)) => {
-                        for item in items {
-                            if
-                                let Err(e) = pipeline_clone.process_item(
-                                    spider_clone.name(),
-                                    item
-                                ).await
-                            {
-                                error!("Pipeline error: {}", e);
-                            }
-                        }
-                        next_requests
-                    }
-                    Err(e) => {
-                        error!("Spider error: {}", e);
-                        vec![]
-                    }
-                }
-            });
+    fn config(args: &[&str]) -> Config {
+        let cli = Cli::try_parse_from(args).unwrap();
+        let mut config = cli.base_config().unwrap();
+        let Commands::Jobs { common, .. } = &cli.command else {
+            panic!("expected the jobs command");
+        };
+        common.apply(&mut config);
+        config
+    }
 
-            handles.push(handle);
-        }
+    #[test]
+    fn options_override_the_config_file_only_when_given() {
+        let file_name = format!("in-scraper-cli-{}.toml", std::process::id());
+        let path = std::env::temp_dir().join(file_name);
+        std::fs::write(&path, "concurrent_requests = 4\nmax_retries = 5\noutput_dir = \"out\"\n")
+            .unwrap();
+        let path = path.display().to_string();
+        let jobs = ["in-scraper", "--config", &path, "jobs", "--keywords", "a", "--location", "b"];
 
-        if !handles.is_empty() {
-            let (result, _, remaining) = futures::future::select_all(handles).await;
-            handles = remaining;
+        let from_file = config(&jobs);
+        let overridden = config(&[&jobs[..], &["-c", "2", "--timeout", "10"]].concat());
+        std::fs::remove_file(&path).unwrap();
 
-            if let Ok(next_requests) = result {
-                request_queue.extend(next_requests);
-            }
-        }
+        assert_eq!(from_file.concurrent_requests, 4);
+        assert_eq!(from_file.max_retries, 5);
+        assert_eq!(from_file.output_dir, "out");
+        assert_eq!(from_file.request_timeout, Config::default().request_timeout);
+        assert_eq!(overridden.concurrent_requests, 2);
+        assert_eq!(overridden.max_retries, 5);
+        assert_eq!(overridden.request_timeout, 10);
     }
 
-    info!("Spider {} completed", spider.name());
-    Ok(())
+    #[test]
+    fn parse_prints_to_stdout_unless_an_output_or_format_is_given() {
+        let parse_config = |args: &[&str]| {
+            let args = [&["in-scraper", "parse", "jobs", "page.html"], args].concat();
+            let cli = Cli::try_parse_from(args).unwrap();
+            let Commands::Parse { output, output_format, .. } = &cli.command else {
+                panic!("expected the parse command");
+            };
+            let mut config = Config::default();
+            output_format.apply_parse(&mut config, output.as_ref());
+            config
+        };
+
+        assert_eq!(parse_config(&[]).output_format, "stdout");
+
+        let csv = parse_config(&["--format", "csv"]);
+        assert_eq!(csv.output_format, "csv");
+        assert_eq!(csv.output_dir, Config::default().output_dir);
+
+        let sqlite = parse_config(&["-o", "out", "--format", "sqlite"]);
+        assert_eq!(sqlite.output_format, "sqlite");
+        assert_eq!(sqlite.output_dir, "out");
+        assert_eq!(parse_config(&["-o", "out"]).output_format, Config::default().output_format);
+    }
 }
@@ -0,0 +1,706 @@
+use url::Url;
+use std::sync::Arc;
+use tracing::{ debug, error };
+use crate::config::Config;
+use async_trait::async_trait;
+use crate::error::ScrapeError;
+use anyhow::{ bail, Result };
+use std::collections::HashMap;
+use crate::spiders::{ Request, Response };
+use crate::engine::{ ResponseArchive, Stats };
+use crate::utils::{ HttpCache, HttpClient, ResponseClassifier, Session };
+use crate::utils::fingerprint::{ HEADER_PROFILE_META, SESSION_META };
+use crate::utils::{ HeaderProfile, HeaderProfilePool, HeaderRotation, ProxyPool, PROXY_META };
+
+#[async_trait]
+pub trait DownloaderMiddleware: Send + Sync {
+    async fn process_request(
+        &self,
+        _request: &mut Request,
+        _spider_name: &str
+    ) -> Result<Option<Response>> {
+        Ok(None)
+    }
+
+    async fn process_response(
+        &self,
+        _request: &Request,
+        response: Response,
+        _spider_name: &str
+    ) -> Result<Response> {
+        Ok(response)
+    }
+
+    async fn process_exception(
+        &self,
+        _request: &Request,
+        _error: &anyhow::Error,
+        _spider_name: &str
+    ) -> Result<Option<Response>> {
+        Ok(None)
+    }
+}
+
+pub struct DefaultHeadersMiddleware {
+    headers: HashMap<String, String>,
+}
+
+impl DefaultHeadersMiddleware {
+    pub fn new(headers: HashMap<String, String>) -> Self {
+        Self { headers }
+    }
+}
+
+#[async_trait]
+impl DownloaderMiddleware for DefaultHeadersMiddleware {
+    async fn process_request(
+        &self,
+        request: &mut Request,
+        _spider_name: &str
+    ) -> Result<Option<Response>> {
+        for (name, value) in &self.headers {
+            request.set_default_header(name, value.clone());
+        }
+        Ok(None)
+    }
+}
+
+pub struct UserAgentMiddleware {
+    user_agent: String,
+}
+
+impl UserAgentMiddleware {
+    pub fn new(user_agent: String) -> Self {
+        Self { user_agent }
+    }
+}
+
+#[async_trait]
+impl DownloaderMiddleware for UserAgentMiddleware {
+    async fn process_request(
+        &self,
+        request: &mut Request,
+        _spider_name: &str
+    ) -> Result<Option<Response>> {
+        request.set_default_header("User-Agent", self.user_agent.clone());
+        Ok(None)
+    }
+}
+
+pub struct HeaderProfileMiddleware {
+    profiles: HeaderProfilePool,
+    proxy_pool: Option<Arc<ProxyPool>>,
+}
+
+impl HeaderProfileMiddleware {
+    pub fn new(profiles: HeaderProfilePool, proxy_pool: Option<Arc<ProxyPool>>) -> Self {
+        Self { profiles, proxy_pool }
+    }
+
+    fn sticky_key(&self, request: &mut Request) -> String {
+        if let Some(session) = request.meta.get(SESSION_META) {
+            return format!("session:{session}");
+        }
+
+        match &self.proxy_pool {
+            Some(pool) if self.profiles.rotation() == HeaderRotation::Sticky => {
+                let label = request.meta
+                    .entry(PROXY_META.to_string())
+                    .or_insert_with(|| pool.select(None).label().to_string());
+                format!("proxy:{label}")
+            }
+            _ => "default".to_string(),
+        }
+    }
+
+    fn apply(profile: &HeaderProfile, request: &mut Request) {
+        let mut headers: Vec<(String, String)> = profile.headers
+            .iter()
+            .map(|(name, value)| {
+                let value = request.header(name).map(str::to_string).unwrap_or(value.clone());
+                (name.clone(), value)
+            })
+            .collect();
+
+        for (name, value) in request.headers.drain(..) {
+            if !headers.iter().any(|(existing, _)| existing.eq_ignore_ascii_case(&name)) {
+                headers.push((name, value));
+            }
+        }
+        request.headers = headers;
+    }
+}
+
+#[async_trait]
+impl DownloaderMiddleware for HeaderProfileMiddleware {
+    async fn process_request(
+        &self,
+        request: &mut Request,
+        _spider_name: &str
+    ) -> Result<Option<Response>> {
+        let profile = match request.meta.get(HEADER_PROFILE_META) {
+            Some(name) =>
+                match self.profiles.get(name) {
+                    Some(profile) => profile,
+                    None => bail!("Unknown header profile: {name}"),
+                }
+            None => {
+                let key = self.sticky_key(request);
+                self.profiles.select(&key)
+            }
+        };
+
+        debug!("Using header profile {} for {}", profile.name, request.url);
+        Self::apply(profile, request);
+        Ok(None)
+    }
+}
+
+pub struct SessionMiddleware {
+    session: Session,
+}
+
+impl SessionMiddleware {
+    pub fn new(session: Session) -> Self {
+        Self { session }
+    }
+
+    fn is_linkedin(request: &Request) -> bool {
+        Url::parse(&request.url)
+            .ok()
+            .is_some_and(|url| url.host_str().is_some_and(Session::is_linkedin_host))
+    }
+
+    fn is_login_redirect(response: &Response) -> bool {
+        let Ok(url) = Url::parse(&response.url) else {
+            return false;
+        };
+        let path = url.path();
+        ["/login", "/uas/login", "/checkpoint/lg/login", "/authwall"]
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+    }
+}
+
+#[async_trait]
+impl DownloaderMiddleware for SessionMiddleware {
+    async fn process_request(
+        &self,
+        request: &mut Request,
+        _spider_name: &str
+    ) -> Result<Option<Response>> {
+        if !Self::is_linkedin(request) {
+            return Ok(None);
+        }
+        if let Some(csrf_token) = self.session.csrf_token() {
+            request.set_header("csrf-token", csrf_token);
+        }
+        Ok(None)
+    }
+
+    async fn process_response(
+        &self,
+        request: &Request,
+        response: Response,
+        _spider_name: &str
+    ) -> Result<Response> {
+        let cleared = response.header("set-cookie").is_some_and(|cookies| {
+            cookies.contains("li_at=delete")
+        });
+
+        if Self::is_login_redirect(&response) || cleared {
+            return Err(
+                (ScrapeError::SessionExpired {
+                    url: request.url.clone(),
+                    redirected_to: response.url,
+                }).into()
+            );
+        }
+        Ok(response)
+    }
+}
+
+pub const CACHED_META: &str = "cached";
+
+pub struct HttpCacheMiddleware {
+    cache: HttpCache,
+    stats: Arc<Stats>,
+}
+
+impl HttpCacheMiddleware {
+    pub fn new(cache: HttpCache, stats: Arc<Stats>) -> Self {
+        Self { cache, stats }
+    }
+}
+
+#[async_trait]
+impl DownloaderMiddleware for HttpCacheMiddleware {
+    async fn process_request(
+        &self,
+        request: &mut Request,
+        spider_name: &str
+    ) -> Result<Option<Response>> {
+        if let Some(response) = self.cache.retrieve(spider_name, request) {
+            debug!("Serving {} from HTTP cache", request.url);
+            self.stats.inc_value("httpcache/hit");
+            request.meta.insert(CACHED_META.to_string(), "true".to_string());
+            return Ok(Some(response));
+        }
+
+        self.stats.inc_value("httpcache/miss");
+        if self.cache.is_offline() {
+            return Err((ScrapeError::CacheMiss { url: request.url.clone() }).into());
+        }
+        Ok(None)
+    }
+
+    async fn process_response(
+        &self,
+        request: &Request,
+        response: Response,
+        spider_name: &str
+    ) -> Result<Response> {
+        if !request.meta.contains_key(CACHED_META) {
+            self.cache.store(spider_name, request, &response)?;
+            self.stats.inc_value("httpcache/store");
+        }
+        Ok(response)
+    }
+}
+
+pub struct BanDetectionMiddleware;
+
+impl Default for BanDetectionMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BanDetectionMiddleware {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl DownloaderMiddleware for BanDetectionMiddleware {
+    async fn process_response(
+        &self,
+        _request: &Request,
+        response: Response,
+        _spider_name: &str
+    ) -> Result<Response> {
+        match ResponseClassifier::classify(&response) {
+            Some(error) => Err(error.into()),
+            None => Ok(response),
+        }
+    }
+}
+
+pub struct Downloader {
+    http_client: HttpClient,
+    middlewares: Vec<Box<dyn DownloaderMiddleware>>,
+    archive: Option<(ResponseArchive, Arc<Stats>)>,
+}
+
+impl Downloader {
+    pub fn new(http_client: HttpClient) -> Self {
+        Self { http_client, middlewares: Vec::new(), archive: None }
+    }
+
+    pub fn with_archive(mut self, archive: ResponseArchive, stats: Arc<Stats>) -> Self {
+        self.archive = Some((archive, stats));
+        self
+    }
+
+    pub fn from_config(
+        config: Arc<Config>,
+        http_client: HttpClient,
+        stats: Arc<Stats>
+    ) -> Result<Self> {
+        let mut downloader = Self::new(http_client);
+        for name in &config.downloader_middlewares {
+            match name.as_str() {
+                "http_cache" => {
+                    if let Some(cache) = HttpCache::from_config(&config)? {
+                        downloader.push(HttpCacheMiddleware::new(cache, stats.clone()));
+                    }
+                }
+                "ban_detection" => downloader.push(BanDetectionMiddleware::new()),
+                "header_profiles" => {
+                    let profiles = HeaderProfilePool::from_config(&config)?;
+                    let proxy_pool = downloader.http_client.proxy_pool().cloned();
+                    downloader.push(HeaderProfileMiddleware::new(profiles, proxy_pool));
+                }
+                "default_headers" => {
+                    downloader.push(DefaultHeadersMiddleware::new(config.default_headers.clone()));
+                }
+                "user_agent" => {
+                    downloader.push(UserAgentMiddleware::new(config.user_agent.clone()));
+                }
+                "session" => {
+                    if let Some(session) = Session::from_config(&config)? {
+                        session.install(downloader.http_client.cookie_jar());
+                        downloader.push(SessionMiddleware::new(session));
+                    }
+                }
+                _ => bail!("Unknown downloader middleware: {name}"),
+            }
+        }
+        Ok(downloader)
+    }
+
+    pub fn push(&mut self, middleware: impl DownloaderMiddleware + 'static) {
+        self.middlewares.push(Box::new(middleware));
+    }
+
+    pub fn push_boxed(&mut self, middleware: Box<dyn DownloaderMiddleware>) {
+        self.middlewares.push(middleware);
+    }
+
+    pub async fn fetch(&self, request: &Request, spider_name: &str) -> Result<Response> {
+        let original = request;
+        let mut request = request.clone();
+
+        let mut response = None;
+        for middleware in &self.middlewares {
+            if let Some(short_circuit) = middleware.process_request(&mut request, spider_name).await? {
+                response = Some(short_circuit);
+                break;
+            }
+        }
+
+        let response = match response {
+            Some(response) => response,
+            None => {
+                let downloaded = match self.http_client.download(&request).await {
+                    Ok(response) => {
+                        let response = self.archive(original, response, spider_name).await;
+                        HttpClient::check_status(response)
+                    }
+                    Err(e) => Err(e),
+                };
+                match downloaded {
+                    Ok(response) => response,
+                    Err(e) => self.process_exception(&request, e, spider_name).await?,
+                }
+            }
+        };
+
+        let mut response = self.archive(original, response, spider_name).await;
+        for middleware in self.middlewares.iter().rev() {
+            response = middleware.process_response(&request, response, spider_name).await?;
+        }
+        Ok(response)
+    }
+
+    async fn archive(
+        &self,
+        request: &Request,
+        mut response: Response,
+        spider_name: &str
+    ) -> Response {
+        let Some((archive, stats)) = &self.archive else {
+            return response;
+        };
+        if response.archive_id.is_some() {
+            return response;
+        }
+
+        match archive.store(spider_name, request, &response).await {
+            Ok(id) => {
+                stats.inc_value("archive/response_count");
+                response.archive_id = Some(id);
+            }
+            Err(e) => {
+                error!("Archive error for {}: {:#}", request.url, e);
+                stats.inc_value("archive/error_count");
+            }
+        }
+        response
+    }
+
+    async fn process_exception(
+        &self,
+        request: &Request,
+        error: anyhow::Error,
+        spider_name: &str
+    ) -> Result<Response> {
+        for middleware in self.middlewares.iter().rev() {
+            if let Some(response) = middleware.process_exception(request, &error, spider_name).await? {
+                return Ok(response);
+            }
+        }
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::TestServer;
+
+    fn session_middleware() -> SessionMiddleware {
+        let config = Config {
+            li_at: Some("AQEDAT".to_string()),
+            jsessionid: Some("\"ajax:123\"".to_string()),
+            ..Config::default()
+        };
+        SessionMiddleware::new(Session::from_config(&config).unwrap().unwrap())
+    }
+
+    #[tokio::test]
+    async fn csrf_token_is_only_sent_to_linkedin() {
+        let middleware = session_middleware();
+        let mut linkedin = Request::new("https://www.linkedin.com/in/someone".to_string());
+        let mut other = Request::new("https://example.com/linkedin.com".to_string());
+        let mut lookalike = Request::new("https://notlinkedin.com/".to_string());
+
+        for request in [&mut linkedin, &mut other, &mut lookalike] {
+            middleware.process_request(request, "test").await.unwrap();
+        }
+        assert_eq!(linkedin.header("csrf-token"), Some("ajax:123"));
+        assert_eq!(other.header("csrf-token"), None);
+        assert_eq!(lookalike.header("csrf-token"), None);
+    }
+
+    #[tokio::test]
+    async fn login_redirect_is_a_session_error() {
+        let middleware = session_middleware();
+        let request = Request::new("https://www.linkedin.com/in/someone".to_string());
+        let response = Response {
+            url: "https://www.linkedin.com/authwall?trk=x".to_string(),
+            status: 200,
+            headers: HashMap::new(),
+            body: String::new(),
+            proxy: None,
+            archive_id: None,
+        };
+
+        let error = middleware.process_response(&request, response, "test").await.unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(ScrapeError::SessionExpired { .. })));
+    }
+
+    fn header_middleware() -> HeaderProfileMiddleware {
+        let profiles = HeaderProfilePool::from_config(&Config::default()).unwrap();
+        HeaderProfileMiddleware::new(profiles, None)
+    }
+
+    fn header_names(request: &Request) -> Vec<&str> {
+        request.headers.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn header_profile_meta_selects_the_profile() {
+        let middleware = header_middleware();
+        let safari = middleware.profiles.get("safari_17_macos").unwrap().clone();
+        let mut request = Request::new("https://www.linkedin.com/in/someone".to_string())
+            .with_meta(HEADER_PROFILE_META.to_string(), safari.name.clone());
+
+        middleware.process_request(&mut request, "test").await.unwrap();
+        assert_eq!(request.header("user-agent"), safari.user_agent());
+
+        let mut unknown = Request::new("https://www.linkedin.com/in/someone".to_string())
+            .with_meta(HEADER_PROFILE_META.to_string(), "netscape_4".to_string());
+        assert!(middleware.process_request(&mut unknown, "test").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sticky_profiles_follow_the_session_meta() {
+        let middleware = header_middleware();
+        let mut user_agents = Vec::new();
+        for _ in 0..10 {
+            let mut request = Request::new("https://www.linkedin.com/in/someone".to_string())
+                .with_meta(SESSION_META.to_string(), "a".to_string());
+            middleware.process_request(&mut request, "test").await.unwrap();
+            user_agents.push(request.header("user-agent").unwrap().to_string());
+        }
+        assert!(user_agents.iter().all(|user_agent| *user_agent == user_agents[0]));
+    }
+
+    #[test]
+    fn applied_profiles_keep_header_order_and_explicit_headers() {
+        let profile = HeaderProfile {
+            name: "test".to_string(),
+            headers: vec![
+                ("sec-ch-ua".to_string(), "\"Chromium\"".to_string()),
+                ("User-Agent".to_string(), "Profile/1.0".to_string()),
+                ("Accept".to_string(), "text/html".to_string()),
+            ],
+        };
+        let mut request = Request::new("https://www.linkedin.com/in/someone".to_string());
+        request.headers = vec![
+            ("X-Trace".to_string(), "1".to_string()),
+            ("user-agent".to_string(), "Custom/2.0".to_string()),
+        ];
+
+        HeaderProfileMiddleware::apply(&profile, &mut request);
+        assert_eq!(header_names(&request), ["sec-ch-ua", "User-Agent", "Accept", "X-Trace"]);
+        assert_eq!(request.header("user-agent"), Some("Custom/2.0"));
+        assert_eq!(request.header("accept"), Some("text/html"));
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        name: &'static str,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+        respond: bool,
+        recover: bool,
+    }
+
+    impl Recorder {
+        fn record(&self, hook: &str) {
+            self.log.lock().unwrap().push(format!("{}:{hook}", self.name));
+        }
+
+        fn response(&self, request: &Request) -> Response {
+            Response {
+                url: request.url.clone(),
+                status: 200,
+                headers: HashMap::new(),
+                body: format!("from {}", self.name),
+                proxy: None,
+                archive_id: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DownloaderMiddleware for Recorder {
+        async fn process_request(
+            &self,
+            request: &mut Request,
+            _spider_name: &str
+        ) -> Result<Option<Response>> {
+            self.record("request");
+            Ok(self.respond.then(|| self.response(request)))
+        }
+
+        async fn process_response(
+            &self,
+            _request: &Request,
+            response: Response,
+            _spider_name: &str
+        ) -> Result<Response> {
+            self.record("response");
+            Ok(response)
+        }
+
+        async fn process_exception(
+            &self,
+            request: &Request,
+            _error: &anyhow::Error,
+            _spider_name: &str
+        ) -> Result<Option<Response>> {
+            self.record("exception");
+            Ok(self.recover.then(|| self.response(request)))
+        }
+    }
+
+    fn downloader(middlewares: Vec<Recorder>) -> Downloader {
+        let config = Arc::new(Config { max_retries: 0, ..Config::default() });
+        let mut downloader = Downloader::new(HttpClient::new(config).unwrap());
+        for middleware in middlewares {
+            downloader.push(middleware);
+        }
+        downloader
+    }
+
+    fn recorders(
+        log: &Arc<std::sync::Mutex<Vec<String>>>,
+        configure: impl Fn(&mut Recorder)
+    ) -> Vec<Recorder> {
+        ["a", "b", "c"]
+            .into_iter()
+            .map(|name| {
+                let mut recorder = Recorder { name, log: log.clone(), ..Default::default() };
+                configure(&mut recorder);
+                recorder
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn requests_run_forward_and_responses_in_reverse() {
+        let server = TestServer::start(|_| (200, "live".to_string())).await;
+        let log = Arc::default();
+        let downloader = downloader(recorders(&log, |_| {}));
+
+        let request = Request::new(format!("{}/page", server.url));
+        let response = downloader.fetch(&request, "test").await.unwrap();
+        assert_eq!(response.body, "live");
+        assert_eq!(*log.lock().unwrap(), [
+            "a:request",
+            "b:request",
+            "c:request",
+            "c:response",
+            "b:response",
+            "a:response",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn process_request_can_answer_without_the_network() {
+        let server = TestServer::start(|_| (200, "live".to_string())).await;
+        let log = Arc::default();
+        let downloader = downloader(recorders(&log, |recorder| {
+            recorder.respond = recorder.name == "b";
+        }));
+
+        let request = Request::new(format!("{}/page", server.url));
+        let response = downloader.fetch(&request, "test").await.unwrap();
+        assert_eq!(response.body, "from b");
+        assert!(server.requests().is_empty());
+        assert!(!log.lock().unwrap().contains(&"c:request".to_string()));
+    }
+
+    #[tokio::test]
+    async fn process_exception_can_recover_from_a_failed_download() {
+        let log = Arc::default();
+        let downloader = downloader(recorders(&log, |recorder| {
+            recorder.recover = recorder.name == "b";
+        }));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/page", listener.local_addr().unwrap());
+        drop(listener);
+
+        let response = downloader.fetch(&Request::new(url), "test").await.unwrap();
+        assert_eq!(response.body, "from b");
+        let log = log.lock().unwrap();
+        let expected = ["c:exception", "b:exception", "c:response", "b:response", "a:response"];
+        assert_eq!(log[3..], expected);
+    }
+
+    #[tokio::test]
+    async fn archives_responses_before_ban_detection_rejects_them() {
+        let server = TestServer::start(|_| (999, "blocked".to_string())).await;
+        let dir_name = format!("in-scraper-archive-ban-{}", std::process::id());
+        let archive_dir = std::env::temp_dir().join(dir_name);
+        let _ = std::fs::remove_dir_all(&archive_dir);
+        let config = Config {
+            archive_dir: Some(archive_dir.display().to_string()),
+            ..Config::default()
+        };
+        let stats = Arc::new(Stats::new());
+        let archive = ResponseArchive::from_config(&config).unwrap();
+        let mut downloader = downloader(Vec::new()).with_archive(archive, stats.clone());
+        downloader.push(BanDetectionMiddleware::new());
+
+        let request = Request::new(format!("{}/in/someone", server.url));
+        let error = downloader.fetch(&request, "test").await.unwrap_err();
+        let records: Vec<_> = std::fs::read_dir(archive_dir.join("test"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .filter_map(|path| ResponseArchive::load_record(&path))
+            .collect();
+        std::fs::remove_dir_all(&archive_dir).unwrap();
+
+        assert!(matches!(error.downcast_ref(), Some(ScrapeError::Blocked { .. })));
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].status, 999);
+        assert_eq!(records[0].request.url, request.url);
+        assert_eq!(stats.get_value("archive/response_count"), 1);
+    }
+}
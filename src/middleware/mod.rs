@@ -1,3 +1,14 @@
 pub mod spider;
+pub mod downloader;
 
-pub use spider::SpiderMiddleware;
+pub use spider::{ LinkedinSpiderMiddleware, SpiderMiddleware, SpiderMiddlewareChain, SpiderOutput };
+pub use downloader::{
+    Downloader,
+    DownloaderMiddleware,
+    DefaultHeadersMiddleware,
+    UserAgentMiddleware,
+    HeaderProfileMiddleware,
+    SessionMiddleware,
+    HttpCacheMiddleware,
+    BanDetectionMiddleware,
+};
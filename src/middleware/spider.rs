@@ -1,28 +1,41 @@
 use tracing::info;
-use anyhow::Result;
-use serde::Serialize;
+use crate::config::Config;
 use async_trait::async_trait;
+use crate::spiders::{ Request, Response };
+use anyhow::{ bail, Result };
+
+pub enum SpiderOutput<T> {
+    Item(T),
+    Request(Request),
+}
 
 #[async_trait]
-pub trait SpiderMiddleware: Send + Sync {
-    async fn process_spider_input(&self, _response: &str, _spider_name: &str) -> Result<()> {
+pub trait SpiderMiddleware<T: Send + 'static>: Send + Sync {
+    async fn process_spider_input(
+        &self,
+        _response: &Response,
+        _request: &Request,
+        _spider_name: &str
+    ) -> Result<()> {
         Ok(())
     }
 
-    async fn process_spider_output<T: Serialize + Send>(
+    async fn process_spider_output(
         &self,
-        items: Vec<T>,
+        output: Vec<SpiderOutput<T>>,
+        _request: &Request,
         _spider_name: &str
-    ) -> Result<Vec<T>> {
-        Ok(items)
+    ) -> Result<Vec<SpiderOutput<T>>> {
+        Ok(output)
     }
 
     async fn process_spider_exception(
         &self,
         _error: &anyhow::Error,
+        _request: &Request,
         _spider_name: &str
-    ) -> Result<()> {
-        Ok(())
+    ) -> Result<Option<Vec<SpiderOutput<T>>>> {
+        Ok(None)
     }
 }
 
@@ -41,25 +54,199 @@ impl LinkedinSpiderMiddleware {
 }
 
 #[async_trait]
-impl SpiderMiddleware for LinkedinSpiderMiddleware {
-    async fn process_spider_input(&self, _response: &str, _spider_name: &str) -> Result<()> {
+impl<T: Send + 'static> SpiderMiddleware<T> for LinkedinSpiderMiddleware {
+    async fn process_spider_exception(
+        &self,
+        error: &anyhow::Error,
+        request: &Request,
+        spider_name: &str
+    ) -> Result<Option<Vec<SpiderOutput<T>>>> {
+        info!("Spider {} encountered error on {}: {}", spider_name, request.url, error);
+        Ok(None)
+    }
+}
+
+pub struct SpiderMiddlewareChain<T> {
+    middlewares: Vec<Box<dyn SpiderMiddleware<T>>>,
+}
+
+impl<T: Send + 'static> Default for SpiderMiddlewareChain<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send + 'static> SpiderMiddlewareChain<T> {
+    pub fn new() -> Self {
+        Self { middlewares: Vec::new() }
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mut chain = Self::new();
+        for name in &config.spider_middlewares {
+            match name.as_str() {
+                "linkedin" => chain.push(LinkedinSpiderMiddleware::new()),
+                _ => bail!("Unknown spider middleware: {name}"),
+            }
+        }
+        Ok(chain)
+    }
+
+    pub fn push(&mut self, middleware: impl SpiderMiddleware<T> + 'static) {
+        self.middlewares.push(Box::new(middleware));
+    }
+
+    pub async fn process_spider_input(
+        &self,
+        response: &Response,
+        request: &Request,
+        spider_name: &str
+    ) -> Result<()> {
+        for middleware in &self.middlewares {
+            middleware.process_spider_input(response, request, spider_name).await?;
+        }
         Ok(())
     }
 
-    async fn process_spider_output<T: Serialize + Send>(
+    pub async fn process_spider_output(
         &self,
-        items: Vec<T>,
-        _spider_name: &str
-    ) -> Result<Vec<T>> {
-        Ok(items)
+        mut output: Vec<SpiderOutput<T>>,
+        request: &Request,
+        spider_name: &str
+    ) -> Result<Vec<SpiderOutput<T>>> {
+        for middleware in self.middlewares.iter().rev() {
+            output = middleware.process_spider_output(output, request, spider_name).await?;
+        }
+        Ok(output)
     }
 
-    async fn process_spider_exception(
+    pub async fn process_spider_exception(
         &self,
         error: &anyhow::Error,
+        request: &Request,
         spider_name: &str
-    ) -> Result<()> {
-        info!("Spider {} encountered error: {}", spider_name, error);
-        Ok(())
+    ) -> Result<Option<Vec<SpiderOutput<T>>>> {
+        for (index, middleware) in self.middlewares.iter().enumerate().rev() {
+            if
+                let Some(output) = middleware.process_spider_exception(
+                    error,
+                    request,
+                    spider_name
+                ).await?
+            {
+                let remaining = &self.middlewares[..index];
+                let mut output = output;
+                for middleware in remaining.iter().rev() {
+                    output = middleware.process_spider_output(output, request, spider_name).await?;
+                }
+                return Ok(Some(output));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[derive(Default)]
+    struct Rewrite {
+        tag: &'static str,
+        drop: Option<&'static str>,
+        recover: bool,
+    }
+
+    #[async_trait]
+    impl SpiderMiddleware<String> for Rewrite {
+        async fn process_spider_output(
+            &self,
+            output: Vec<SpiderOutput<String>>,
+            _request: &Request,
+            _spider_name: &str
+        ) -> Result<Vec<SpiderOutput<String>>> {
+            Ok(
+                output
+                    .into_iter()
+                    .filter_map(|entry| match entry {
+                        SpiderOutput::Item(item) if Some(item.as_str()) == self.drop => None,
+                        SpiderOutput::Item(item) =>
+                            Some(SpiderOutput::Item(format!("{item}+{}", self.tag))),
+                        SpiderOutput::Request(request) => {
+                            let url = request.url.replace("http://", "https://");
+                            Some(SpiderOutput::Request(Request { url, ..request }))
+                        }
+                    })
+                    .collect()
+            )
+        }
+
+        async fn process_spider_exception(
+            &self,
+            _error: &anyhow::Error,
+            _request: &Request,
+            _spider_name: &str
+        ) -> Result<Option<Vec<SpiderOutput<String>>>> {
+            Ok(self.recover.then(|| vec![SpiderOutput::Item(format!("recovered by {}", self.tag))]))
+        }
+    }
+
+    fn chain(middlewares: Vec<Rewrite>) -> SpiderMiddlewareChain<String> {
+        let mut chain = SpiderMiddlewareChain::new();
+        for middleware in middlewares {
+            chain.push(middleware);
+        }
+        chain
+    }
+
+    fn split(output: Vec<SpiderOutput<String>>) -> (Vec<String>, Vec<String>) {
+        let (mut items, mut urls) = (Vec::new(), Vec::new());
+        for entry in output {
+            match entry {
+                SpiderOutput::Item(item) => items.push(item),
+                SpiderOutput::Request(request) => urls.push(request.url),
+            }
+        }
+        (items, urls)
+    }
+
+    #[tokio::test]
+    async fn output_middlewares_drop_and_replace_entries_in_reverse_order() {
+        let chain = chain(vec![
+            Rewrite { tag: "a", ..Default::default() },
+            Rewrite { tag: "b", drop: Some("spam"), ..Default::default() },
+        ]);
+        let request = Request::new("http://www.linkedin.com/jobs".to_string());
+        let next_page = Request::new("http://www.linkedin.com/jobs?start=25".to_string());
+        let output = vec![
+            SpiderOutput::Item("job".to_string()),
+            SpiderOutput::Item("spam".to_string()),
+            SpiderOutput::Request(next_page),
+        ];
+
+        let output = chain.process_spider_output(output, &request, "test").await.unwrap();
+        let (items, urls) = split(output);
+        assert_eq!(items, ["job+b+a"]);
+        assert_eq!(urls, ["https://www.linkedin.com/jobs?start=25"]);
+    }
+
+    #[tokio::test]
+    async fn recovered_output_passes_through_the_remaining_middlewares() {
+        let chain = chain(vec![
+            Rewrite { tag: "a", ..Default::default() },
+            Rewrite { tag: "b", recover: true, ..Default::default() },
+            Rewrite { tag: "c", ..Default::default() },
+        ]);
+        let request = Request::new("https://www.linkedin.com/jobs".to_string());
+
+        let error = anyhow!("parse failed");
+        let output = chain.process_spider_exception(&error, &request, "test").await.unwrap();
+        let (items, _) = split(output.unwrap());
+        assert_eq!(items, ["recovered by b+a"]);
+
+        let unrecovered = SpiderMiddlewareChain::<String>::new();
+        let output = unrecovered.process_spider_exception(&error, &request, "test").await.unwrap();
+        assert!(output.is_none());
     }
 }
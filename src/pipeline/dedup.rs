@@ -0,0 +1,83 @@
+use anyhow::Result;
+use schemars::Schema;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tracing::debug;
+use crate::engine::Stats;
+use async_trait::async_trait;
+use std::collections::{ HashMap, HashSet };
+use crate::pipeline::{ key_field, ItemPipeline };
+
+#[derive(Default)]
+struct SeenItems {
+    key: Option<String>,
+    keys: HashSet<String>,
+    dropped: u64,
+}
+
+#[derive(Default)]
+pub struct DedupPipeline {
+    spiders: Mutex<HashMap<String, SeenItems>>,
+}
+
+impl DedupPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ItemPipeline for DedupPipeline {
+    async fn open_spider(&self, spider_name: &str, schema: &Schema) -> Result<()> {
+        let seen = SeenItems { key: key_field(schema.as_value()), ..Default::default() };
+        self.spiders.lock().await.insert(spider_name.to_string(), seen);
+        Ok(())
+    }
+
+    async fn process_item(&self, item: Value, spider_name: &str) -> Result<Option<Value>> {
+        let mut spiders = self.spiders.lock().await;
+        let seen = spiders.entry(spider_name.to_string()).or_default();
+        let key = seen.key.as_ref().and_then(|key| item.get(key)).and_then(Value::as_str);
+        let Some(key) = key else {
+            return Ok(Some(item));
+        };
+
+        if !seen.keys.insert(key.to_string()) {
+            debug!("Dropped duplicate item {}", key);
+            seen.dropped += 1;
+            return Ok(None);
+        }
+        Ok(Some(item))
+    }
+
+    async fn close_spider(&self, spider_name: &str, stats: &Stats) -> Result<()> {
+        if let Some(seen) = self.spiders.lock().await.remove(spider_name) {
+            stats.set_value("dedup/dropped_count", seen.dropped);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use schemars::schema_for;
+    use crate::items::JobListing;
+
+    #[tokio::test]
+    async fn drops_items_with_a_seen_key() {
+        let pipeline = DedupPipeline::new();
+        pipeline.open_spider("jobs", &schema_for!(JobListing)).await.unwrap();
+
+        let job = |url: &str, title: &str| json!({ "job_detail_url": url, "job_title": title });
+        let first = pipeline.process_item(job("https://a/1", "Rust"), "jobs").await.unwrap();
+        let repeat = pipeline.process_item(job("https://a/1", "Go"), "jobs").await.unwrap();
+        let other = pipeline.process_item(job("https://a/2", "Rust"), "jobs").await.unwrap();
+        assert!(first.is_some() && repeat.is_none() && other.is_some());
+
+        let stats = Stats::new();
+        pipeline.close_spider("jobs", &stats).await.unwrap();
+        assert_eq!(stats.get_value("dedup/dropped_count"), 1);
+    }
+}
@@ -0,0 +1,426 @@
+use chrono::Local;
+use std::sync::Arc;
+use schemars::Schema;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use crate::config::Config;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fs::{ self, File, OpenOptions };
+use std::path::{ Path, PathBuf };
+use anyhow::{ anyhow, bail, Context, Result };
+use csv::{ Writer, WriterBuilder };
+use crate::pipeline::{ key_field, resolve_ref, ItemPipeline };
+
+const LIST_SEPARATOR: &str = "; ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvLayout {
+    Wide,
+    Tables,
+}
+
+impl CsvLayout {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "wide" => Ok(Self::Wide),
+            "tables" => Ok(Self::Tables),
+            _ => bail!("Unknown CSV layout: {value}, expected wide or tables"),
+        }
+    }
+}
+
+struct ListField {
+    name: String,
+    columns: Vec<String>,
+}
+
+struct RecordLayout {
+    key: Option<String>,
+    columns: Vec<String>,
+    lists: Vec<ListField>,
+}
+
+impl RecordLayout {
+    fn from_schema(schema: &Value) -> Self {
+        let mut layout = Self { key: None, columns: Vec::new(), lists: Vec::new() };
+
+        for (name, property) in Self::properties(schema, schema) {
+            let entries = property
+                .get("items")
+                .map(|items| resolve_ref(schema, items))
+                .filter(|items| items.get("properties").is_some());
+
+            match entries {
+                Some(entries) =>
+                    layout.lists.push(ListField {
+                        name,
+                        columns: Self::properties(schema, entries)
+                            .into_iter()
+                            .map(|(name, _)| name)
+                            .collect(),
+                    }),
+                None => layout.columns.push(name),
+            }
+        }
+
+        layout.key = key_field(schema);
+        layout
+    }
+
+    fn properties<'a>(root: &'a Value, schema: &'a Value) -> Vec<(String, &'a Value)> {
+        schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .map(|(name, property)| (name.clone(), resolve_ref(root, property)))
+            .collect()
+    }
+
+    fn key_value<'a>(&self, item: &'a Value) -> Option<&'a Value> {
+        self.key.as_ref().and_then(|key| item.get(key))
+    }
+}
+
+struct CsvOutput {
+    path: PathBuf,
+    layout: RecordLayout,
+    main: Writer<File>,
+    tables: Vec<Writer<File>>,
+}
+
+pub struct CsvPipeline {
+    config: Arc<Config>,
+    delimiter: u8,
+    extension: &'static str,
+    layout: CsvLayout,
+    schemas: Arc<Mutex<HashMap<String, Value>>>,
+    outputs: Arc<Mutex<HashMap<String, CsvOutput>>>,
+    resume_paths: Arc<Mutex<HashMap<String, PathBuf>>>,
+}
+
+impl CsvPipeline {
+    pub fn new(config: Arc<Config>, delimiter: u8) -> Result<Self> {
+        let extension = if delimiter == b'\t' { "tsv" } else { "csv" };
+        let layout = CsvLayout::parse(&config.csv_layout)?;
+
+        Ok(Self {
+            config,
+            delimiter,
+            extension,
+            layout,
+            schemas: Arc::new(Mutex::new(HashMap::new())),
+            outputs: Arc::new(Mutex::new(HashMap::new())),
+            resume_paths: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    fn open(
+        &self,
+        spider_name: &str,
+        resume_path: Option<PathBuf>,
+        schema: &Value
+    ) -> Result<CsvOutput> {
+        let output_dir = PathBuf::from(&self.config.output_dir);
+        fs::create_dir_all(&output_dir).context("Failed to create output directory")?;
+
+        let path = resume_path.unwrap_or_else(|| {
+            let timestamp = Local::now().format("%d_%m_%Y_%H:%M:%S");
+            output_dir.join(format!("{spider_name}_{timestamp}.{}", self.extension))
+        });
+        let layout = RecordLayout::from_schema(schema);
+
+        let main = match self.layout {
+            CsvLayout::Wide => {
+                let mut header = layout.columns.clone();
+                for list in &layout.lists {
+                    for index in 0..self.config.csv_max_entries {
+                        for column in &list.columns {
+                            header.push(format!("{}_{index}_{column}", list.name));
+                        }
+                    }
+                }
+                self.writer(&path, &header)?
+            }
+            CsvLayout::Tables => self.writer(&path, &layout.columns)?,
+        };
+
+        let mut tables = Vec::new();
+        if self.layout == CsvLayout::Tables {
+            let key = layout.key.clone().unwrap_or_else(|| "key".to_string());
+            for list in &layout.lists {
+                let mut header = vec![key.clone(), "index".to_string()];
+                header.extend(list.columns.iter().cloned());
+                tables.push(self.writer(&Self::table_path(&path, &list.name), &header)?);
+            }
+        }
+
+        Ok(CsvOutput { path, layout, main, tables })
+    }
+
+    fn writer(&self, path: &Path, header: &[String]) -> Result<Writer<File>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open output file {}", path.display()))?;
+        let is_empty = file.metadata()?.len() == 0;
+
+        let mut writer = WriterBuilder::new().delimiter(self.delimiter).from_writer(file);
+        if is_empty {
+            writer.write_record(header)?;
+        }
+        Ok(writer)
+    }
+
+    fn table_path(path: &Path, table: &str) -> PathBuf {
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_string())
+            .unwrap_or_default();
+        path.with_file_name(format!("{stem}_{table}.{extension}"))
+    }
+
+    fn wide_row(&self, layout: &RecordLayout, item: &Value) -> Vec<String> {
+        let mut row: Vec<String> = layout.columns
+            .iter()
+            .map(|column| Self::cell(item.get(column)))
+            .collect();
+
+        for list in &layout.lists {
+            let entries = item.get(&list.name).and_then(Value::as_array);
+            for index in 0..self.config.csv_max_entries {
+                let entry = entries.and_then(|entries| entries.get(index));
+                for column in &list.columns {
+                    row.push(Self::cell(entry.and_then(|entry| entry.get(column))));
+                }
+            }
+        }
+        row
+    }
+
+    fn cell(value: Option<&Value>) -> String {
+        match value {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Array(values)) =>
+                values
+                    .iter()
+                    .map(|value| Self::cell(Some(value)))
+                    .collect::<Vec<_>>()
+                    .join(LIST_SEPARATOR),
+            Some(value) => value.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl ItemPipeline for CsvPipeline {
+    async fn open_spider(&self, spider_name: &str, schema: &Schema) -> Result<()> {
+        self.schemas.lock().await.insert(spider_name.to_string(), schema.as_value().clone());
+        Ok(())
+    }
+
+    async fn resume_output_files(&self, output_files: &HashMap<String, PathBuf>) {
+        let mut resume_paths = self.resume_paths.lock().await;
+        for (spider_name, path) in output_files {
+            if let Some(spider_name) = spider_name.strip_suffix(&format!(".{}", self.extension)) {
+                resume_paths.insert(spider_name.to_string(), path.clone());
+            }
+        }
+    }
+
+    async fn output_files(&self) -> HashMap<String, PathBuf> {
+        self.outputs
+            .lock().await
+            .iter()
+            .map(|(spider_name, output)| {
+                (format!("{spider_name}.{}", self.extension), output.path.clone())
+            })
+            .collect()
+    }
+
+    async fn process_item(&self, item: Value, spider_name: &str) -> Result<Option<Value>> {
+        let resume_path = self.resume_paths.lock().await.get(spider_name).cloned();
+        let mut outputs = self.outputs.lock().await;
+
+        if !outputs.contains_key(spider_name) {
+            let schemas = self.schemas.lock().await;
+            let schema = schemas
+                .get(spider_name)
+                .ok_or_else(|| anyhow!("No item schema registered for spider {spider_name}"))?;
+            let output = self.open(spider_name, resume_path, schema)?;
+            outputs.insert(spider_name.to_string(), output);
+        }
+        let output = outputs.get_mut(spider_name).unwrap();
+
+        match self.layout {
+            CsvLayout::Wide => {
+                output.main.write_record(self.wide_row(&output.layout, &item))?;
+            }
+            CsvLayout::Tables => {
+                let row = output.layout.columns
+                    .iter()
+                    .map(|column| Self::cell(item.get(column)));
+                output.main.write_record(row)?;
+
+                let key = Self::cell(output.layout.key_value(&item));
+                for (list, table) in output.layout.lists.iter().zip(output.tables.iter_mut()) {
+                    let entries = item.get(&list.name).and_then(Value::as_array);
+                    for (index, entry) in entries.into_iter().flatten().enumerate() {
+                        let mut row = vec![key.clone(), index.to_string()];
+                        row.extend(list.columns.iter().map(|column| Self::cell(entry.get(column))));
+                        table.write_record(row)?;
+                    }
+                    table.flush().context("Failed to flush file")?;
+                }
+            }
+        }
+
+        output.main.flush().context("Failed to flush file")?;
+        Ok(Some(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use schemars::schema_for;
+    use crate::items::PersonProfile;
+
+    #[test]
+    fn person_layout_keys_side_tables_by_profile() {
+        let layout = RecordLayout::from_schema(schema_for!(PersonProfile).as_value());
+        let lists: Vec<&str> = layout.lists.iter().map(|list| list.name.as_str()).collect();
+
+        assert_eq!(layout.key.as_deref(), Some("profile"));
+        assert_eq!(lists, ["experience", "education", "projects", "languages", "activities"]);
+        assert!(layout.columns.contains(&"name".to_string()));
+        assert!(!layout.columns.contains(&"experience".to_string()));
+    }
+
+    fn output_dir(name: &str) -> PathBuf {
+        let dir_name = format!("in-scraper-csv-{name}-{}", std::process::id());
+        let dir = std::env::temp_dir().join(dir_name);
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    async fn pipeline(output_dir: &Path, layout: &str) -> CsvPipeline {
+        let config = Config {
+            output_dir: output_dir.display().to_string(),
+            csv_layout: layout.to_string(),
+            csv_max_entries: 2,
+            ..Config::default()
+        };
+        let pipeline = CsvPipeline::new(Arc::new(config), b',').unwrap();
+        pipeline.open_spider("people", &schema_for!(PersonProfile)).await.unwrap();
+        pipeline
+    }
+
+    fn person(profile: &str, titles: &[&str]) -> Value {
+        json!({
+            "schema_version": 3,
+            "profile": profile,
+            "url": format!("https://www.linkedin.com/in/{profile}"),
+            "name": profile.to_uppercase(),
+            "experience": titles.iter().map(|title| json!({ "title": title })).collect::<Vec<_>>(),
+            "education": [],
+            "projects": [],
+            "languages": [{ "name": "English" }],
+            "activities": []
+        })
+    }
+
+    fn read(path: &Path) -> (Vec<String>, Vec<HashMap<String, String>>) {
+        let mut reader = csv::Reader::from_path(path).unwrap();
+        let header: Vec<String> = reader.headers().unwrap().iter().map(String::from).collect();
+        let rows = reader
+            .records()
+            .map(|record| {
+                let record = record.unwrap();
+                header.iter().cloned().zip(record.iter().map(String::from)).collect()
+            })
+            .collect();
+        (header, rows)
+    }
+
+    #[tokio::test]
+    async fn wide_rows_truncate_lists_at_the_entry_limit() {
+        let dir = output_dir("wide");
+        let pipeline = pipeline(&dir, "wide").await;
+        let item = person("jane", &["Lead", "Engineer", "Intern"]);
+        pipeline.process_item(item, "people").await.unwrap();
+
+        let (header, rows) = read(&pipeline.output_files().await["people.csv"]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(header.contains(&"experience_1_title".to_string()));
+        assert!(!header.iter().any(|column| column.starts_with("experience_2_")));
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["name"], "JANE");
+        assert_eq!(rows[0]["experience_0_title"], "Lead");
+        assert_eq!(rows[0]["experience_1_title"], "Engineer");
+        assert_eq!(rows[0]["languages_0_name"], "English");
+        assert_eq!(rows[0]["languages_1_name"], "");
+    }
+
+    #[tokio::test]
+    async fn side_tables_are_keyed_by_profile() {
+        let dir = output_dir("tables");
+        let pipeline = pipeline(&dir, "tables").await;
+        let jane = person("jane", &["Lead", "Engineer", "Intern"]);
+        pipeline.process_item(jane, "people").await.unwrap();
+        pipeline.process_item(person("john", &["Founder"]), "people").await.unwrap();
+
+        let path = pipeline.output_files().await["people.csv"].clone();
+        let (header, people) = read(&path);
+        let (experience_header, experience) = read(&CsvPipeline::table_path(&path, "experience"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!header.contains(&"experience".to_string()));
+        assert_eq!(people.len(), 2);
+        assert_eq!(experience_header[..2], ["profile", "index"]);
+        let rows: Vec<(&str, &str, &str)> = experience
+            .iter()
+            .map(|row| (row["profile"].as_str(), row["index"].as_str(), row["title"].as_str()))
+            .collect();
+        assert_eq!(rows, [
+            ("jane", "0", "Lead"),
+            ("jane", "1", "Engineer"),
+            ("jane", "2", "Intern"),
+            ("john", "0", "Founder"),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn resumed_files_do_not_repeat_the_header() {
+        let dir = output_dir("resume");
+        let first = pipeline(&dir, "tables").await;
+        first.process_item(person("jane", &["Lead"]), "people").await.unwrap();
+        let output_files = first.output_files().await;
+        drop(first);
+
+        let resumed = pipeline(&dir, "tables").await;
+        resumed.resume_output_files(&output_files).await;
+        resumed.process_item(person("john", &["Founder"]), "people").await.unwrap();
+
+        let path = &output_files["people.csv"];
+        let main = fs::read_to_string(path).unwrap();
+        let (_, people) = read(path);
+        let (_, experience) = read(&CsvPipeline::table_path(path, "experience"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(resumed.output_files().await, output_files);
+        assert_eq!(main.matches("schema_version").count(), 1);
+        let profiles: Vec<&str> = people.iter().map(|row| row["profile"].as_str()).collect();
+        assert_eq!(profiles, ["jane", "john"]);
+        let titles: Vec<&str> = experience.iter().map(|row| row["title"].as_str()).collect();
+        assert_eq!(titles, ["Lead", "Founder"]);
+    }
+}
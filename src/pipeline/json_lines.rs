@@ -1,36 +1,58 @@
 use chrono::Local;
 use std::io::Write;
 use std::sync::Arc;
-use serde::Serialize;
+use serde_json::Value;
 use std::path::PathBuf;
 use tokio::sync::Mutex;
 use crate::config::Config;
+use crate::pipeline::ItemPipeline;
+use async_trait::async_trait;
 use anyhow::{ Result, Context };
+use std::collections::HashMap;
 use std::fs::{ self, OpenOptions };
 
 pub struct JsonPipeline {
     config: Arc<Config>,
-    file_handles: Arc<Mutex<std::collections::HashMap<String, (std::fs::File, PathBuf)>>>,
+    file_handles: Arc<Mutex<HashMap<String, (std::fs::File, PathBuf)>>>,
+    resume_paths: Arc<Mutex<HashMap<String, PathBuf>>>,
 }
 
 impl JsonPipeline {
     pub fn new(config: Arc<Config>) -> Self {
         Self {
             config,
-            file_handles: Arc::new(Mutex::new(std::collections::HashMap::new())),
+            file_handles: Arc::new(Mutex::new(HashMap::new())),
+            resume_paths: Arc::new(Mutex::new(HashMap::new())),
         }
     }
+}
+
+#[async_trait]
+impl ItemPipeline for JsonPipeline {
+    async fn resume_output_files(&self, output_files: &HashMap<String, PathBuf>) {
+        self.resume_paths.lock().await.extend(output_files.clone());
+    }
 
-    pub async fn process_item<T: Serialize>(&self, spider_name: &str, item: T) -> Result<()> {
+    async fn output_files(&self) -> HashMap<String, PathBuf> {
+        self.file_handles
+            .lock().await
+            .iter()
+            .map(|(spider_name, (_, path))| (spider_name.clone(), path.clone()))
+            .collect()
+    }
+
+    async fn process_item(&self, item: Value, spider_name: &str) -> Result<Option<Value>> {
         let output_dir = PathBuf::from(&self.config.output_dir);
         fs::create_dir_all(&output_dir).context("Failed to create output directory")?;
 
+        let resume_path = self.resume_paths.lock().await.get(spider_name).cloned();
         let mut handles = self.file_handles.lock().await;
 
         let (file, _) = handles.entry(spider_name.to_string()).or_insert_with(|| {
-            let timestamp = Local::now().format("%d_%m_%Y_%H:%M:%S");
-            let filename = format!("{spider_name}_{timestamp}.jsonl");
-            let filepath = output_dir.join(&filename);
+            let filepath = resume_path.unwrap_or_else(|| {
+                let timestamp = Local::now().format("%d_%m_%Y_%H:%M:%S");
+                output_dir.join(format!("{spider_name}_{timestamp}.jsonl"))
+            });
 
             let file = OpenOptions::new()
                 .create(true)
@@ -47,6 +69,39 @@ impl JsonPipeline {
 
         file.flush().context("Failed to flush file")?;
 
-        Ok(())
+        Ok(Some(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn appends_to_the_resumed_output_file() {
+        let dir_name = format!("in-scraper-jsonl-{}", std::process::id());
+        let output_dir = std::env::temp_dir().join(dir_name);
+        let _ = fs::remove_dir_all(&output_dir);
+        let config = Arc::new(Config {
+            output_dir: output_dir.display().to_string(),
+            ..Config::default()
+        });
+
+        let first = JsonPipeline::new(config.clone());
+        first.process_item(json!({ "id": 1 }), "jobs").await.unwrap();
+        let output_files = first.output_files().await;
+
+        let resumed = JsonPipeline::new(config);
+        resumed.resume_output_files(&output_files).await;
+        resumed.process_item(json!({ "id": 2 }), "jobs").await.unwrap();
+
+        let path = &output_files["jobs"];
+        let content = fs::read_to_string(path).unwrap();
+        let files = fs::read_dir(&output_dir).unwrap().count();
+        fs::remove_dir_all(&output_dir).unwrap();
+        assert_eq!(resumed.output_files().await, output_files);
+        assert_eq!(content, "{\"id\":1}\n{\"id\":2}\n");
+        assert_eq!(files, 1);
     }
 }
@@ -1,3 +1,172 @@
 pub mod json_lines;
+pub mod delimited;
+pub mod sqlite;
+pub mod stdout;
+pub mod dedup;
+pub mod validation;
+pub mod normalize;
 
 pub use json_lines::JsonPipeline;
+pub use delimited::{ CsvLayout, CsvPipeline };
+pub use sqlite::SqlitePipeline;
+pub use stdout::StdoutPipeline;
+pub use dedup::DedupPipeline;
+pub use validation::ValidationPipeline;
+pub use normalize::NormalizationPipeline;
+
+use std::sync::Arc;
+use schemars::Schema;
+use serde_json::Value;
+use std::path::PathBuf;
+use crate::config::Config;
+use crate::engine::Stats;
+use async_trait::async_trait;
+use anyhow::{ bail, Result };
+use std::collections::HashMap;
+
+const KEY_KEYWORD: &str = "x-key";
+
+#[async_trait]
+pub trait ItemPipeline: Send + Sync {
+    async fn open_spider(&self, _spider_name: &str, _schema: &Schema) -> Result<()> {
+        Ok(())
+    }
+
+    async fn process_item(&self, item: Value, spider_name: &str) -> Result<Option<Value>>;
+
+    async fn close_spider(&self, _spider_name: &str, _stats: &Stats) -> Result<()> {
+        Ok(())
+    }
+
+    async fn resume_output_files(&self, _output_files: &HashMap<String, PathBuf>) {}
+
+    async fn output_files(&self) -> HashMap<String, PathBuf> {
+        HashMap::new()
+    }
+}
+
+pub struct ItemPipelineChain {
+    pipelines: Vec<Box<dyn ItemPipeline>>,
+}
+
+impl Default for ItemPipelineChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ItemPipelineChain {
+    pub fn new() -> Self {
+        Self { pipelines: Vec::new() }
+    }
+
+    pub fn from_config(config: Arc<Config>) -> Result<Self> {
+        let mut chain = Self::new();
+        for name in &config.item_pipelines {
+            let name = match name.as_str() {
+                "output" => config.output_format.as_str(),
+                name => name,
+            };
+            match name {
+                "jsonl" | "json" => chain.push(JsonPipeline::new(config.clone())),
+                "csv" => chain.push(CsvPipeline::new(config.clone(), b',')?),
+                "tsv" => chain.push(CsvPipeline::new(config.clone(), b'\t')?),
+                "sqlite" => chain.push(SqlitePipeline::new(config.clone())?),
+                "stdout" => chain.push(StdoutPipeline::new()),
+                "dedup" => chain.push(DedupPipeline::new()),
+                "validate" => chain.push(ValidationPipeline::new(config.clone())),
+                "normalize" => chain.push(NormalizationPipeline::new()),
+                _ => bail!("Unknown item pipeline: {name}"),
+            }
+        }
+        Ok(chain)
+    }
+
+    pub fn push(&mut self, pipeline: impl ItemPipeline + 'static) {
+        self.pipelines.push(Box::new(pipeline));
+    }
+
+    pub async fn open_spider(&self, spider_name: &str, schema: &Schema) -> Result<()> {
+        for pipeline in &self.pipelines {
+            pipeline.open_spider(spider_name, schema).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn process_item(&self, mut item: Value, spider_name: &str) -> Result<Option<Value>> {
+        for pipeline in &self.pipelines {
+            match pipeline.process_item(item, spider_name).await? {
+                Some(processed) => {
+                    item = processed;
+                }
+                None => {
+                    return Ok(None);
+                }
+            }
+        }
+        Ok(Some(item))
+    }
+
+    pub async fn close_spider(&self, spider_name: &str, stats: &Stats) -> Result<()> {
+        let mut result = Ok(());
+        for pipeline in &self.pipelines {
+            if let Err(e) = pipeline.close_spider(spider_name, stats).await {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    pub async fn resume_output_files(&self, output_files: HashMap<String, PathBuf>) {
+        for pipeline in &self.pipelines {
+            pipeline.resume_output_files(&output_files).await;
+        }
+    }
+
+    pub async fn output_files(&self) -> HashMap<String, PathBuf> {
+        let mut output_files = HashMap::new();
+        for pipeline in &self.pipelines {
+            output_files.extend(pipeline.output_files().await);
+        }
+        output_files
+    }
+}
+
+pub(crate) fn key_field(schema: &Value) -> Option<String> {
+    schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .find(|(_, property)| property.get(KEY_KEYWORD).and_then(Value::as_bool) == Some(true))
+        .map(|(name, _)| name.clone())
+}
+
+pub(crate) fn resolve_ref<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix("#/$defs/"))
+        .and_then(|name| root.get("$defs")?.get(name))
+        .unwrap_or(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::archive::ArchivedItem;
+    use crate::items::{ CompanyProfile, JobListing, PersonProfile };
+
+    fn key(schema: Schema) -> Option<String> {
+        key_field(schema.as_value())
+    }
+
+    #[test]
+    fn key_field_is_the_declared_item_key() {
+        assert_eq!(key(ArchivedItem::<CompanyProfile>::schema(false)).unwrap(), "url");
+        assert_eq!(key(ArchivedItem::<JobListing>::schema(false)).unwrap(), "job_detail_url");
+        assert_eq!(key(ArchivedItem::<PersonProfile>::schema(false)).unwrap(), "profile");
+        assert_eq!(key(ArchivedItem::<PersonProfile>::schema(true)).unwrap(), "profile");
+        assert_eq!(key(Schema::default()), None);
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use serde_json::Value;
+use async_trait::async_trait;
+use chrono::{ DateTime, NaiveDate, Utc };
+use crate::pipeline::ItemPipeline;
+use crate::utils::date_utils::{ months_between, posting_date, MonthDate };
+
+pub struct NormalizationPipeline;
+
+impl Default for NormalizationPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NormalizationPipeline {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn crawled_at(item: &Value) -> Option<DateTime<Utc>> {
+        let crawled_at = item.get("crawled_at").and_then(Value::as_str)?;
+        Some(DateTime::parse_from_rfc3339(crawled_at).ok()?.with_timezone(&Utc))
+    }
+
+    fn normalize_period(entry: &mut Value, today: Option<NaiveDate>, with_duration: bool) {
+        let date = |field: &str| {
+            entry.get(field).and_then(Value::as_str).and_then(MonthDate::parse)
+        };
+        let (start, end) = (date("start_time"), date("end_time"));
+        let Some(entry) = entry.as_object_mut() else {
+            return;
+        };
+
+        entry.insert("start_date".to_string(), start.and_then(|start| start.iso()).into());
+        entry.insert("end_date".to_string(), end.and_then(|end| end.iso()).into());
+        if with_duration {
+            let months = start
+                .zip(end)
+                .zip(today)
+                .and_then(|((start, end), today)| months_between(start, end, today));
+            entry.insert("duration_months".to_string(), months.into());
+        }
+    }
+}
+
+#[async_trait]
+impl ItemPipeline for NormalizationPipeline {
+    async fn process_item(&self, mut item: Value, _spider_name: &str) -> Result<Option<Value>> {
+        let crawled_at = Self::crawled_at(&item);
+        let today = crawled_at.map(|crawled_at| crawled_at.date_naive());
+
+        if let Some(item) = item.as_object_mut().filter(|item| item.contains_key("job_listed")) {
+            let listed = item
+                .get("job_listed")
+                .and_then(Value::as_str)
+                .zip(crawled_at)
+                .and_then(|(listed, crawled_at)| posting_date(listed, crawled_at))
+                .map(|date| date.format("%Y-%m-%d").to_string());
+            item.insert("job_listed_date".to_string(), listed.into());
+        }
+
+        for (list, with_duration) in [("experience", true), ("education", false)] {
+            let entries = item.get_mut(list).and_then(Value::as_array_mut);
+            for entry in entries.into_iter().flatten() {
+                Self::normalize_period(entry, today, with_duration);
+            }
+        }
+
+        Ok(Some(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn dates_are_normalized_against_the_crawl_time() {
+        let pipeline = NormalizationPipeline::new();
+        let person = json!({
+            "crawled_at": "2024-03-15T10:00:00Z",
+            "experience": [
+                { "start_time": "Jan 2020", "end_time": "Mar 2022" },
+                { "start_time": "Nov 2023", "end_time": "Present" }
+            ],
+            "education": [{ "start_time": "2014", "end_time": "2018" }]
+        });
+        let person = pipeline.process_item(person, "people").await.unwrap().unwrap();
+
+        assert_eq!(person["experience"][0]["start_date"], "2020-01");
+        assert_eq!(person["experience"][0]["duration_months"], 27);
+        assert_eq!(person["experience"][1]["end_date"], Value::Null);
+        assert_eq!(person["experience"][1]["duration_months"], 5);
+        assert_eq!(person["education"][0]["end_date"], "2018");
+        assert!(person["education"][0].get("duration_months").is_none());
+
+        let job = json!({ "crawled_at": "2024-03-15T10:00:00Z", "job_listed": "2 weeks ago" });
+        let job = pipeline.process_item(job, "jobs").await.unwrap().unwrap();
+        assert_eq!(job["job_listed_date"], "2024-03-01");
+
+        let job = json!({ "job_listed": "2 weeks ago" });
+        let job = pipeline.process_item(job, "jobs").await.unwrap().unwrap();
+        assert_eq!(job["job_listed_date"], Value::Null);
+    }
+}
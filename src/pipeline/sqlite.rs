@@ -0,0 +1,435 @@
+use std::fs;
+use tracing::info;
+use chrono::{ SecondsFormat, Utc };
+use std::sync::Arc;
+use std::sync::Mutex;
+use serde_json::Value;
+use std::path::PathBuf;
+use crate::config::Config;
+use async_trait::async_trait;
+use crate::pipeline::ItemPipeline;
+use tokio::task;
+use anyhow::{ bail, Context, Result };
+use rusqlite::{ params, Connection, Transaction };
+use crate::items::{ CompanyProfile, JobListing, PersonProfile };
+use crate::spiders::{ CompanyProfileSpider, JobsSpider, PeopleProfileSpider };
+
+const DEFAULT_DATABASE: &str = "linkedin.db";
+
+const SCHEMA: &str = "
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE IF NOT EXISTS companies (
+    url TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    summary TEXT,
+    industry TEXT,
+    size TEXT,
+    founded TEXT,
+    schema_version INTEGER NOT NULL,
+    response_id TEXT,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS jobs (
+    job_detail_url TEXT PRIMARY KEY,
+    job_title TEXT,
+    job_listed TEXT,
+    job_listed_date TEXT,
+    company_name TEXT,
+    company_link TEXT,
+    company_location TEXT,
+    schema_version INTEGER NOT NULL,
+    response_id TEXT,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS people (
+    profile TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    location TEXT,
+    followers TEXT,
+    connections TEXT,
+    schema_version INTEGER NOT NULL,
+    response_id TEXT,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS experience (
+    profile TEXT NOT NULL REFERENCES people(profile) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    title TEXT,
+    organization_profile TEXT,
+    location TEXT,
+    description TEXT,
+    duration TEXT,
+    duration_months INTEGER,
+    start_time TEXT,
+    end_time TEXT,
+    start_date TEXT,
+    end_date TEXT,
+    logo TEXT,
+    PRIMARY KEY (profile, position)
+);
+
+CREATE TABLE IF NOT EXISTS education (
+    profile TEXT NOT NULL REFERENCES people(profile) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    organization TEXT,
+    organization_profile TEXT,
+    course_details TEXT,
+    description TEXT,
+    start_time TEXT,
+    end_time TEXT,
+    start_date TEXT,
+    end_date TEXT,
+    PRIMARY KEY (profile, position)
+);
+
+CREATE TABLE IF NOT EXISTS projects (
+    profile TEXT NOT NULL REFERENCES people(profile) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    name TEXT,
+    description TEXT,
+    url TEXT,
+    PRIMARY KEY (profile, position)
+);
+
+CREATE TABLE IF NOT EXISTS languages (
+    profile TEXT NOT NULL REFERENCES people(profile) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    name TEXT,
+    proficiency TEXT,
+    PRIMARY KEY (profile, position)
+);
+
+CREATE TABLE IF NOT EXISTS activities (
+    profile TEXT NOT NULL REFERENCES people(profile) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    title TEXT,
+    url TEXT,
+    PRIMARY KEY (profile, position)
+);
+";
+
+const CHILD_TABLES: [&str; 5] = ["experience", "education", "projects", "languages", "activities"];
+
+enum Record {
+    Company(CompanyProfile),
+    Job(JobListing),
+    Person(PersonProfile),
+}
+
+impl Record {
+    fn from_item(spider_name: &str, value: Value) -> Result<Self> {
+        let record = match spider_name {
+            CompanyProfileSpider::NAME => Self::Company(serde_json::from_value(value)?),
+            JobsSpider::NAME => Self::Job(serde_json::from_value(value)?),
+            PeopleProfileSpider::NAME => Self::Person(serde_json::from_value(value)?),
+            _ => bail!("No SQLite table for items from spider {spider_name}"),
+        };
+        Ok(record)
+    }
+
+    fn upsert(
+        &self,
+        transaction: &Transaction,
+        response_id: &Option<String>,
+        seen: &str
+    ) -> Result<()> {
+        match self {
+            Self::Company(company) =>
+                SqlitePipeline::upsert_company(transaction, company, response_id, seen),
+            Self::Job(job) => SqlitePipeline::upsert_job(transaction, job, response_id, seen),
+            Self::Person(person) =>
+                SqlitePipeline::upsert_person(transaction, person, response_id, seen),
+        }
+    }
+}
+
+pub struct SqlitePipeline {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqlitePipeline {
+    pub fn new(config: Arc<Config>) -> Result<Self> {
+        let path = match &config.sqlite_path {
+            Some(path) => PathBuf::from(path),
+            None => PathBuf::from(&config.output_dir).join(DEFAULT_DATABASE),
+        };
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).context("Failed to create output directory")?;
+        }
+
+        let connection = Connection::open(&path).with_context(||
+            format!("Failed to open SQLite database {}", path.display())
+        )?;
+        connection.execute_batch(SCHEMA).context("Failed to create SQLite schema")?;
+        info!("Writing items to SQLite database {}", path.display());
+
+        Ok(Self { connection: Arc::new(Mutex::new(connection)) })
+    }
+
+    fn upsert_company(
+        transaction: &Transaction,
+        company: &CompanyProfile,
+        response_id: &Option<String>,
+        seen: &str
+    ) -> Result<()> {
+        transaction.execute(
+            "INSERT INTO companies (url, name, summary, industry, size, founded, schema_version,
+                response_id, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
+             ON CONFLICT (url) DO UPDATE SET
+                name = excluded.name, summary = excluded.summary, industry = excluded.industry,
+                size = excluded.size, founded = excluded.founded,
+                schema_version = excluded.schema_version, response_id = excluded.response_id,
+                last_seen = excluded.last_seen",
+            params![
+                company.url,
+                company.name,
+                company.summary,
+                company.industry,
+                company.size,
+                company.founded,
+                company.schema_version,
+                response_id,
+                seen
+            ]
+        )?;
+        Ok(())
+    }
+
+    fn upsert_job(
+        transaction: &Transaction,
+        job: &JobListing,
+        response_id: &Option<String>,
+        seen: &str
+    ) -> Result<()> {
+        transaction.execute(
+            "INSERT INTO jobs (job_detail_url, job_title, job_listed, job_listed_date, company_name,
+                company_link, company_location, schema_version, response_id, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)
+             ON CONFLICT (job_detail_url) DO UPDATE SET
+                job_title = excluded.job_title, job_listed = excluded.job_listed,
+                job_listed_date = excluded.job_listed_date,
+                company_name = excluded.company_name, company_link = excluded.company_link,
+                company_location = excluded.company_location,
+                schema_version = excluded.schema_version, response_id = excluded.response_id,
+                last_seen = excluded.last_seen",
+            params![
+                job.job_detail_url,
+                job.job_title,
+                job.job_listed,
+                job.job_listed_date,
+                job.company_name,
+                job.company_link,
+                job.company_location,
+                job.schema_version,
+                response_id,
+                seen
+            ]
+        )?;
+        Ok(())
+    }
+
+    fn upsert_person(
+        transaction: &Transaction,
+        person: &PersonProfile,
+        response_id: &Option<String>,
+        seen: &str
+    ) -> Result<()> {
+        transaction.execute(
+            "INSERT INTO people (profile, url, name, description, location, followers, connections,
+                schema_version, response_id, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)
+             ON CONFLICT (profile) DO UPDATE SET
+                url = excluded.url, name = excluded.name, description = excluded.description,
+                location = excluded.location, followers = excluded.followers,
+                connections = excluded.connections, schema_version = excluded.schema_version,
+                response_id = excluded.response_id, last_seen = excluded.last_seen",
+            params![
+                person.profile,
+                person.url,
+                person.name,
+                person.description,
+                person.location,
+                person.followers,
+                person.connections,
+                person.schema_version,
+                response_id,
+                seen
+            ]
+        )?;
+
+        for table in CHILD_TABLES {
+            let statement = format!("DELETE FROM {table} WHERE profile = ?1");
+            transaction.execute(&statement, [&person.profile])?;
+        }
+
+        for (position, entry) in person.experience.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO experience (profile, position, title, organization_profile, location,
+                    description, duration, duration_months, start_time, end_time, start_date,
+                    end_date, logo)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    person.profile,
+                    position,
+                    entry.title,
+                    entry.organization_profile,
+                    entry.location,
+                    entry.description,
+                    entry.duration,
+                    entry.duration_months,
+                    entry.start_time,
+                    entry.end_time,
+                    entry.start_date,
+                    entry.end_date,
+                    entry.logo
+                ]
+            )?;
+        }
+
+        for (position, entry) in person.education.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO education (profile, position, organization, organization_profile,
+                    course_details, description, start_time, end_time, start_date, end_date)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    person.profile,
+                    position,
+                    entry.organization,
+                    entry.organization_profile,
+                    entry.course_details,
+                    entry.description,
+                    entry.start_time,
+                    entry.end_time,
+                    entry.start_date,
+                    entry.end_date
+                ]
+            )?;
+        }
+
+        for (position, entry) in person.projects.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO projects (profile, position, name, description, url)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![person.profile, position, entry.name, entry.description, entry.url]
+            )?;
+        }
+
+        for (position, entry) in person.languages.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO languages (profile, position, name, proficiency)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![person.profile, position, entry.name, entry.proficiency]
+            )?;
+        }
+
+        for (position, entry) in person.activities.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO activities (profile, position, title, url) VALUES (?1, ?2, ?3, ?4)",
+                params![person.profile, position, entry.title, entry.url]
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ItemPipeline for SqlitePipeline {
+    async fn process_item(&self, item: Value, spider_name: &str) -> Result<Option<Value>> {
+        let response_id = item.get("response_id").and_then(Value::as_str).map(String::from);
+        let record = Record::from_item(spider_name, item.clone()).context(
+            "Unsupported item for SQLite output"
+        )?;
+        let seen = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let connection = self.connection.clone();
+        task::spawn_blocking(move || {
+            let mut connection = connection.lock().unwrap();
+            let transaction = connection.transaction()?;
+            record.upsert(&transaction, &response_id, &seen)?;
+            transaction.commit().context("Failed to commit item")
+        }).await.context("SQLite writer task failed")??;
+        Ok(Some(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pipeline(name: &str) -> (SqlitePipeline, PathBuf) {
+        let file_name = format!("in-scraper-{name}-{}.db", std::process::id());
+        let path = std::env::temp_dir().join(file_name);
+        let _ = fs::remove_file(&path);
+        let config = Config { sqlite_path: Some(path.display().to_string()), ..Config::default() };
+        (SqlitePipeline::new(Arc::new(config)).unwrap(), path)
+    }
+
+    fn count(pipeline: &SqlitePipeline, table: &str) -> i64 {
+        let connection = pipeline.connection.lock().unwrap();
+        let query = format!("SELECT COUNT(*) FROM {table}");
+        connection.query_row(&query, [], |row| row.get(0)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn upserts_keep_one_row_per_item_and_replace_list_entries() {
+        let (pipeline, path) = pipeline("upsert");
+        let person = |name: &str, titles: &[&str]| json!({
+            "schema_version": 3,
+            "profile": "jane",
+            "url": "https://www.linkedin.com/in/jane",
+            "name": name,
+            "experience": titles.iter().map(|title| json!({ "title": title })).collect::<Vec<_>>(),
+            "education": [],
+            "projects": [],
+            "languages": [],
+            "activities": [],
+            "response_id": "abc"
+        });
+
+        let spider = PeopleProfileSpider::NAME;
+        pipeline.process_item(person("Jane", &["Engineer", "Intern"]), spider).await.unwrap();
+        pipeline.process_item(person("Jane Doe", &["Lead"]), spider).await.unwrap();
+
+        assert_eq!(count(&pipeline, "people"), 1);
+        assert_eq!(count(&pipeline, "experience"), 1);
+        let (name, response_id): (String, String) = pipeline.connection
+            .lock()
+            .unwrap()
+            .query_row("SELECT name, response_id FROM people", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!((name.as_str(), response_id.as_str()), ("Jane Doe", "abc"));
+    }
+
+    #[tokio::test]
+    async fn items_are_routed_by_spider_name() {
+        let (pipeline, path) = pipeline("routing");
+        let company = json!({
+            "schema_version": 3,
+            "url": "https://www.linkedin.com/company/acme",
+            "name": "Acme",
+            "profile": "acme"
+        });
+
+        pipeline.process_item(company.clone(), CompanyProfileSpider::NAME).await.unwrap();
+        let unknown = pipeline.process_item(company, "other").await;
+        let (companies, people) = (count(&pipeline, "companies"), count(&pipeline, "people"));
+        fs::remove_file(&path).unwrap();
+        assert_eq!((companies, people), (1, 0));
+        assert!(unknown.is_err());
+    }
+}
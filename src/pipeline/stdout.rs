@@ -0,0 +1,26 @@
+use anyhow::Result;
+use serde_json::Value;
+use async_trait::async_trait;
+use crate::pipeline::ItemPipeline;
+
+pub struct StdoutPipeline;
+
+impl Default for StdoutPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StdoutPipeline {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ItemPipeline for StdoutPipeline {
+    async fn process_item(&self, item: Value, _spider_name: &str) -> Result<Option<Value>> {
+        println!("{}", serde_json::to_string(&item)?);
+        Ok(Some(item))
+    }
+}
@@ -0,0 +1,338 @@
+use url::Url;
+use regex::Regex;
+use std::io::{ self, Write };
+use std::sync::Arc;
+use tracing::warn;
+use schemars::Schema;
+use tokio::sync::Mutex;
+use std::path::PathBuf;
+use crate::config::Config;
+use crate::engine::Stats;
+use serde_json::{ json, Value };
+use async_trait::async_trait;
+use std::fs::{ self, OpenOptions };
+use anyhow::{ anyhow, Context, Result };
+use std::collections::{ BTreeMap, HashMap };
+use crate::pipeline::{ resolve_ref, ItemPipeline };
+
+const REJECTED_DIR: &str = "rejected";
+
+enum Check {
+    MinLength(u64),
+    Url,
+    Pattern(Regex),
+}
+
+impl Check {
+    fn name(&self) -> &'static str {
+        match self {
+            Check::MinLength(_) => "min_length",
+            Check::Url => "url",
+            Check::Pattern(_) => "pattern",
+        }
+    }
+
+    fn failure(&self, value: &Value) -> Option<String> {
+        match (self, value) {
+            (Check::MinLength(min), Value::String(text))
+                if (text.trim().chars().count() as u64) < *min
+            => Some(format!("must have at least {min} characters")),
+            (Check::Url, Value::String(text)) if Url::parse(text).is_err() =>
+                Some(format!("is not a valid URL: {text:?}")),
+            (Check::Pattern(pattern), Value::String(text)) if !pattern.is_match(text) =>
+                Some(format!("does not match {}: {text:?}", pattern.as_str())),
+            _ => None,
+        }
+    }
+}
+
+struct FieldRule {
+    name: String,
+    required: bool,
+    checks: Vec<Check>,
+    entries: Vec<FieldRule>,
+}
+
+impl FieldRule {
+    fn from_schema(root: &Value, schema: &Value) -> Result<Vec<Self>> {
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+
+        let mut rules = Vec::new();
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (name, property) in properties.into_iter().flatten() {
+            let property = resolve_ref(root, property);
+            let mut checks = Vec::new();
+
+            if let Some(min) = property.get("minLength").and_then(Value::as_u64) {
+                checks.push(Check::MinLength(min));
+            }
+            if property.get("format").and_then(Value::as_str) == Some("uri") {
+                checks.push(Check::Url);
+            }
+            if let Some(pattern) = property.get("pattern").and_then(Value::as_str) {
+                let pattern = Regex
+                    ::new(pattern)
+                    .with_context(|| format!("Invalid pattern for {name}"))?;
+                checks.push(Check::Pattern(pattern));
+            }
+
+            let entries = match property.get("items").map(|items| resolve_ref(root, items)) {
+                Some(items) if items.get("properties").is_some() => Self::from_schema(root, items)?,
+                _ => Vec::new(),
+            };
+
+            rules.push(Self {
+                name: name.clone(),
+                required: required.contains(&name.as_str()),
+                checks,
+                entries,
+            });
+        }
+        Ok(rules)
+    }
+
+    fn validate(
+        rules: &[Self],
+        item: &Value,
+        path: &str,
+        field: &str,
+        failures: &mut Vec<Failure>
+    ) {
+        for rule in rules {
+            let path = format!("{path}{}", rule.name);
+            let field = format!("{field}{}", rule.name);
+            let value = item.get(&rule.name).unwrap_or(&Value::Null);
+
+            if value.is_null() {
+                if rule.required {
+                    failures.push(Failure {
+                        key: format!("{field}/required"),
+                        reason: format!("{path} is required"),
+                    });
+                }
+                continue;
+            }
+
+            for check in &rule.checks {
+                if let Some(reason) = check.failure(value) {
+                    failures.push(Failure {
+                        key: format!("{field}/{}", check.name()),
+                        reason: format!("{path} {reason}"),
+                    });
+                }
+            }
+
+            for (index, entry) in value.as_array().into_iter().flatten().enumerate() {
+                let (path, field) = (format!("{path}[{index}]."), format!("{field}[]."));
+                Self::validate(&rule.entries, entry, &path, &field, failures);
+            }
+        }
+    }
+}
+
+struct Failure {
+    key: String,
+    reason: String,
+}
+
+#[derive(Default)]
+struct SpiderValidation {
+    rules: Vec<FieldRule>,
+    rejected: Option<Box<dyn Write + Send>>,
+    counts: BTreeMap<String, u64>,
+}
+
+pub struct ValidationPipeline {
+    config: Arc<Config>,
+    spiders: Mutex<HashMap<String, SpiderValidation>>,
+}
+
+impl ValidationPipeline {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self { config, spiders: Mutex::new(HashMap::new()) }
+    }
+
+    fn rejected_writer(&self, spider_name: &str) -> Result<Box<dyn Write + Send>> {
+        if self.config.output_format == "stdout" {
+            return Ok(Box::new(io::stderr()));
+        }
+
+        let dir = PathBuf::from(&self.config.output_dir).join(REJECTED_DIR);
+        fs::create_dir_all(&dir).context("Failed to create rejected items directory")?;
+
+        let path = dir.join(format!("{spider_name}.jsonl"));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open rejected items file {}", path.display()))?;
+        Ok(Box::new(file))
+    }
+}
+
+#[async_trait]
+impl ItemPipeline for ValidationPipeline {
+    async fn open_spider(&self, spider_name: &str, schema: &Schema) -> Result<()> {
+        let rules = FieldRule::from_schema(schema.as_value(), schema.as_value())?;
+        let validation = SpiderValidation { rules, ..Default::default() };
+        self.spiders.lock().await.insert(spider_name.to_string(), validation);
+        Ok(())
+    }
+
+    async fn process_item(&self, item: Value, spider_name: &str) -> Result<Option<Value>> {
+        let mut spiders = self.spiders.lock().await;
+        let validation = spiders
+            .get_mut(spider_name)
+            .ok_or_else(|| anyhow!("No item schema registered for spider {spider_name}"))?;
+
+        let mut failures = Vec::new();
+        FieldRule::validate(&validation.rules, &item, "", "", &mut failures);
+        if failures.is_empty() {
+            return Ok(Some(item));
+        }
+
+        let reasons: Vec<String> = failures.iter().map(|failure| failure.reason.clone()).collect();
+        warn!("Rejected item from {}: {}", spider_name, reasons.join(", "));
+        for failure in failures {
+            *validation.counts.entry(failure.key).or_insert(0) += 1;
+        }
+        *validation.counts.entry("rejected_count".to_string()).or_insert(0) += 1;
+
+        if validation.rejected.is_none() {
+            validation.rejected = Some(self.rejected_writer(spider_name)?);
+        }
+        let writer = validation.rejected.as_mut().unwrap();
+        let record = json!({ "reasons": reasons, "item": item });
+        writeln!(writer, "{}", serde_json::to_string(&record)?)
+            .context("Failed to write rejected item")?;
+        writer.flush().context("Failed to flush rejected items")?;
+
+        Ok(None)
+    }
+
+    async fn close_spider(&self, spider_name: &str, stats: &Stats) -> Result<()> {
+        if let Some(validation) = self.spiders.lock().await.remove(spider_name) {
+            for (key, count) in validation.counts {
+                stats.set_value(&format!("validation/{key}"), count);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schemars::schema_for;
+    use crate::items::{ JobListing, PersonProfile };
+
+    fn person() -> Value {
+        json!({
+            "schema_version": 3,
+            "profile": "jane",
+            "url": "https://www.linkedin.com/in/jane",
+            "name": "Jane",
+            "experience": [{ "start_time": "Jan 2020", "end_time": "Present" }],
+            "education": [],
+            "projects": [],
+            "languages": [],
+            "activities": []
+        })
+    }
+
+    #[tokio::test]
+    async fn rejects_items_that_break_schema_rules() {
+        let dir_name = format!("in-scraper-rejects-{}", std::process::id());
+        let output_dir = std::env::temp_dir().join(dir_name);
+        let config = Config { output_dir: output_dir.display().to_string(), ..Config::default() };
+        let pipeline = ValidationPipeline::new(Arc::new(config));
+        pipeline.open_spider("people", &schema_for!(PersonProfile)).await.unwrap();
+
+        assert!(pipeline.process_item(person(), "people").await.unwrap().is_some());
+
+        let mut broken = person();
+        broken["name"] = json!("  ");
+        broken["url"] = json!("/in/jane");
+        broken["experience"][0]["start_time"] = json!("sometime in 2020");
+        broken.as_object_mut().unwrap().remove("profile");
+        assert!(pipeline.process_item(broken, "people").await.unwrap().is_none());
+
+        let stats = Stats::new();
+        pipeline.close_spider("people", &stats).await.unwrap();
+        let rejected = fs::read_to_string(output_dir.join(REJECTED_DIR).join("people.jsonl"));
+        fs::remove_dir_all(&output_dir).unwrap();
+
+        let record: Value = serde_json::from_str(rejected.unwrap().trim()).unwrap();
+        assert_eq!(record["reasons"].as_array().unwrap().len(), 4);
+        assert_eq!(record["item"]["name"], "  ");
+        for key in [
+            "rejected_count",
+            "profile/required",
+            "name/min_length",
+            "url/url",
+            "experience[].start_time/pattern",
+        ] {
+            assert_eq!(stats.get_value(&format!("validation/{key}")), 1, "{key}");
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_rejects_out_of_the_output_dir_when_printing_to_stdout() {
+        let dir_name = format!("in-scraper-stdout-rejects-{}", std::process::id());
+        let output_dir = std::env::temp_dir().join(dir_name);
+        let config = Config {
+            output_dir: output_dir.display().to_string(),
+            output_format: "stdout".to_string(),
+            ..Config::default()
+        };
+        let pipeline = ValidationPipeline::new(Arc::new(config));
+        pipeline.open_spider("people", &schema_for!(PersonProfile)).await.unwrap();
+
+        let mut broken = person();
+        broken["name"] = json!("");
+        assert!(pipeline.process_item(broken, "people").await.unwrap().is_none());
+
+        let stats = Stats::new();
+        pipeline.close_spider("people", &stats).await.unwrap();
+        assert_eq!(stats.get_value("validation/rejected_count"), 1);
+        assert!(!output_dir.exists());
+    }
+
+    #[tokio::test]
+    async fn accepts_posting_times_that_the_normalizer_understands() {
+        let pipeline = ValidationPipeline::new(Arc::new(Config::default()));
+        pipeline.open_spider("jobs", &schema_for!(JobListing)).await.unwrap();
+        let job = |listed: &str| json!({
+            "schema_version": 3,
+            "job_detail_url": "https://www.linkedin.com/jobs/view/1",
+            "job_listed": listed
+        });
+
+        for listed in [
+            "2 weeks ago",
+            "Reposted 30+ minutes ago",
+            "1 day ago",
+            "Just now",
+            "today",
+            "Yesterday",
+            "2023-12-24",
+        ] {
+            let item = pipeline.process_item(job(listed), "jobs").await.unwrap();
+            assert!(item.is_some(), "{listed}");
+        }
+
+        let rules = &pipeline.spiders.lock().await["jobs"].rules;
+        for listed in ["3 fortnights ago", "last week", "24/12/2023", "ago"] {
+            let mut failures = Vec::new();
+            FieldRule::validate(rules, &job(listed), "", "", &mut failures);
+            let keys: Vec<&str> = failures.iter().map(|failure| failure.key.as_str()).collect();
+            assert_eq!(keys, ["job_listed/pattern"], "{listed}");
+        }
+    }
+}
@@ -1,8 +1,29 @@
-pub struct CompanySelectors;
+use serde::{ Deserialize, Serialize };
+use crate::utils::selector_utils::SelectorSpec;
 
-impl CompanySelectors {
-    pub const NAME: &'static str = ".top-card-layout__entity-info h1";
-    pub const SUMMARY: &'static str = ".top-card-layout__entity-info h4 span";
-    pub const DETAILS: &'static str = ".core-section-container__content .mb-2";
-    pub const TEXT_MD: &'static str = ".text-md";
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompanySelectors {
+    pub name: SelectorSpec,
+    pub summary: SelectorSpec,
+    pub details: SelectorSpec,
+    pub text_md: SelectorSpec,
+}
+
+impl Default for CompanySelectors {
+    fn default() -> Self {
+        Self {
+            name: [
+                ".top-card-layout__entity-info h1",
+                "h1.top-card-layout__title",
+                "section.top-card-layout h1",
+            ].into(),
+            summary: [
+                ".top-card-layout__entity-info h4 span",
+                "h4.top-card-layout__second-subline",
+            ].into(),
+            details: ".core-section-container__content .mb-2".into(),
+            text_md: ".text-md".into(),
+        }
+    }
 }
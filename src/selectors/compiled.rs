@@ -0,0 +1,153 @@
+use std::sync::Arc;
+use anyhow::Result;
+use crate::selectors::{ CompanySelectors, JobSelectors, PeopleSelectors, SelectorConfig };
+use crate::utils::selector_utils::{ SelectorChain, SelectorHits, SelectorSpec };
+
+pub struct CompiledSelectors {
+    pub people: PeopleChains,
+    pub company: CompanyChains,
+    pub jobs: JobChains,
+}
+
+impl CompiledSelectors {
+    pub fn new(selectors: &SelectorConfig, hits: Arc<SelectorHits>) -> Result<Self> {
+        Ok(Self {
+            people: PeopleChains::new(&selectors.people, hits.clone())?,
+            company: CompanyChains::new(&selectors.company, hits.clone())?,
+            jobs: JobChains::new(&selectors.jobs, hits)?,
+        })
+    }
+}
+
+pub struct PeopleChains {
+    pub summary: SelectorChain,
+    pub name: SelectorChain,
+    pub description: SelectorChain,
+    pub location: SelectorChain,
+    pub followers: SelectorChain,
+    pub connections: SelectorChain,
+    pub subline_item: SelectorChain,
+    pub exp_item: SelectorChain,
+    pub exp_title: SelectorChain,
+    pub exp_location: SelectorChain,
+    pub exp_desc_more: SelectorChain,
+    pub exp_desc_less: SelectorChain,
+    pub exp_date_time: SelectorChain,
+    pub exp_duration: SelectorChain,
+    pub exp_company_logo: SelectorChain,
+    pub edu_item: SelectorChain,
+    pub edu_org: SelectorChain,
+    pub edu_link: SelectorChain,
+    pub edu_details: SelectorChain,
+    pub edu_desc: SelectorChain,
+    pub edu_date_time: SelectorChain,
+    pub projects_items: SelectorChain,
+    pub project_title: SelectorChain,
+    pub project_description: SelectorChain,
+    pub project_link: SelectorChain,
+    pub languages_items: SelectorChain,
+    pub language_name: SelectorChain,
+    pub language_proficiency: SelectorChain,
+    pub activities_items: SelectorChain,
+    pub activity_title: SelectorChain,
+    pub activity_link: SelectorChain,
+}
+
+impl PeopleChains {
+    fn new(selectors: &PeopleSelectors, hits: Arc<SelectorHits>) -> Result<Self> {
+        let chain = |field: &str, spec: &SelectorSpec| {
+            SelectorChain::new(&format!("people.{field}"), spec, hits.clone())
+        };
+
+        Ok(Self {
+            summary: chain("top_card_layout", &selectors.top_card_layout)?,
+            name: chain("name", &selectors.name)?,
+            description: chain("description", &selectors.description)?,
+            location: chain("location", &selectors.location)?,
+            followers: chain("followers", &selectors.followers)?,
+            connections: chain("connections", &selectors.connections)?,
+            subline_item: chain("subline_item", &selectors.subline_item)?,
+            exp_item: chain("experience_item", &selectors.experience_item)?,
+            exp_title: chain("experience_title", &selectors.experience_title)?,
+            exp_location: chain("experience_location", &selectors.experience_location)?,
+            exp_desc_more: chain(
+                "experience_description_more",
+                &selectors.experience_description_more
+            )?,
+            exp_desc_less: chain(
+                "experience_description_less",
+                &selectors.experience_description_less
+            )?,
+            exp_date_time: chain("experience_date_time", &selectors.experience_date_time)?,
+            exp_duration: chain("experience_duration", &selectors.experience_duration)?,
+            exp_company_logo: chain(
+                "experience_education_company_logo",
+                &selectors.experience_education_company_logo
+            )?,
+            edu_item: chain("education_item", &selectors.education_item)?,
+            edu_org: chain("education_organization", &selectors.education_organization)?,
+            edu_link: chain("education_link", &selectors.education_link)?,
+            edu_details: chain("education_details", &selectors.education_details)?,
+            edu_desc: chain("education_description", &selectors.education_description)?,
+            edu_date_time: chain("education_date_time", &selectors.education_date_time)?,
+            projects_items: chain("projects_items", &selectors.projects_items)?,
+            project_title: chain("project_title", &selectors.project_title)?,
+            project_description: chain("project_description", &selectors.project_description)?,
+            project_link: chain("project_link", &selectors.project_link)?,
+            languages_items: chain("languages_items", &selectors.languages_items)?,
+            language_name: chain("language_name", &selectors.language_name)?,
+            language_proficiency: chain("language_proficiency", &selectors.language_proficiency)?,
+            activities_items: chain("activities_items", &selectors.activities_items)?,
+            activity_title: chain("activity_title", &selectors.activity_title)?,
+            activity_link: chain("activity_link", &selectors.activity_link)?,
+        })
+    }
+}
+
+pub struct CompanyChains {
+    pub name: SelectorChain,
+    pub summary: SelectorChain,
+    pub details: SelectorChain,
+    pub text_md: SelectorChain,
+}
+
+impl CompanyChains {
+    fn new(selectors: &CompanySelectors, hits: Arc<SelectorHits>) -> Result<Self> {
+        let chain = |field: &str, spec: &SelectorSpec| {
+            SelectorChain::new(&format!("company.{field}"), spec, hits.clone())
+        };
+
+        Ok(Self {
+            name: chain("name", &selectors.name)?,
+            summary: chain("summary", &selectors.summary)?,
+            details: chain("details", &selectors.details)?,
+            text_md: chain("text_md", &selectors.text_md)?,
+        })
+    }
+}
+
+pub struct JobChains {
+    pub item: SelectorChain,
+    pub title: SelectorChain,
+    pub url: SelectorChain,
+    pub time: SelectorChain,
+    pub company_name: SelectorChain,
+    pub location: SelectorChain,
+}
+
+impl JobChains {
+    fn new(selectors: &JobSelectors, hits: Arc<SelectorHits>) -> Result<Self> {
+        let chain = |field: &str, spec: &SelectorSpec| {
+            SelectorChain::new(&format!("jobs.{field}"), spec, hits.clone())
+        };
+
+        Ok(Self {
+            item: chain("item", &selectors.item)?,
+            title: chain("title", &selectors.title)?,
+            url: chain("url", &selectors.url)?,
+            time: chain("time", &selectors.time)?,
+            company_name: chain("company_name", &selectors.company_name)?,
+            location: chain("location", &selectors.location)?,
+        })
+    }
+}
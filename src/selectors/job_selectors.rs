@@ -1,10 +1,29 @@
-pub struct JobSelectors;
+use serde::{ Deserialize, Serialize };
+use crate::utils::selector_utils::SelectorSpec;
 
-impl JobSelectors {
-    pub const ITEM: &'static str = "li";
-    pub const TITLE: &'static str = "h3";
-    pub const URL: &'static str = ".base-card__full-link";
-    pub const TIME: &'static str = "time";
-    pub const COMPANY_NAME: &'static str = "h4 a";
-    pub const LOCATION: &'static str = ".job-search-card__location";
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobSelectors {
+    pub item: SelectorSpec,
+    pub title: SelectorSpec,
+    pub url: SelectorSpec,
+    pub time: SelectorSpec,
+    pub company_name: SelectorSpec,
+    pub location: SelectorSpec,
+}
+
+impl Default for JobSelectors {
+    fn default() -> Self {
+        Self {
+            item: "li".into(),
+            title: "h3".into(),
+            url: [".base-card__full-link", "a[href*='/jobs/view/']"].into(),
+            time: "time".into(),
+            company_name: ["h4 a", "a.hidden-nested-link"].into(),
+            location: [
+                ".job-search-card__location",
+                ".base-search-card__metadata > span:first-child",
+            ].into(),
+        }
+    }
 }
@@ -1,7 +1,11 @@
 pub mod people_selectors;
 pub mod company_selectors;
 pub mod job_selectors;
+pub mod store;
+pub mod compiled;
 
 pub use people_selectors::PeopleSelectors;
 pub use company_selectors::CompanySelectors;
 pub use job_selectors::JobSelectors;
+pub use store::{ SelectorConfig, SelectorStore };
+pub use compiled::{ CompanyChains, CompiledSelectors, JobChains, PeopleChains };
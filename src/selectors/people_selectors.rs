@@ -1,38 +1,82 @@
-pub struct PeopleSelectors;
+use serde::{ Deserialize, Serialize };
+use crate::utils::selector_utils::SelectorSpec;
 
-impl PeopleSelectors {
-    pub const TOP_CARD_LAYOUT: &str = "section.top-card-layout";
-    pub const NAME: &str = "h1.top-card-layout__title";
-    pub const DESCRIPTION: &str = "span.top-card-link__description";
-    pub const LOCATION: &str = ".profile-info-subheader span:first-child";
-    pub const FOLLOWERS: &str = ".not-first-middot span:first-child";
-    pub const CONNECTIONS: &str = ".not-first-middot span:last-child";
-    pub const SUBLINE_ITEM: &str = "span.top-card__subline-item";
-    pub const PROJECTS_ITEMS: &str = "section[data-section='projects'] ul > li.personal-project";
-    pub const PROJECT_TITLE: &str = "h3";
-    pub const PROJECT_DESCRIPTION: &str = "p.show-more-less-text__text--less";
-    pub const PROJECT_LINK: &str = "h3 a";
-    pub const LANGUAGES_ITEMS: &str =
-        "section[data-section='languages'] ul > li.profile-section-card";
-    pub const LANGUAGE_NAME: &str = "h3";
-    pub const LANGUAGE_PROFICIENCY: &str = "h4";
-    pub const ACTIVITIES_ITEMS: &str =
-        "section[data-section='posts'] ul[data-test-id='activities__list'] > li";
-    pub const ACTIVITY_TITLE: &str = "h3.base-main-card__title";
-    pub const ACTIVITY_LINK: &str = "a.base-card__full-link";
-    pub const EXPERIENCE_ITEM: &str = "li.profile-section-card";
-    pub const EXPERIENCE_EDUCATION_COMPANY_LOGO: &str =
-        "li.profile-section-card img.profile-section-card__image";
-    pub const EXPERIENCE_TITLE: &str = "h4 > p:first-child";
-    pub const EXPERIENCE_LOCATION: &str = "div.text-color-text-low-emphasis";
-    pub const EXPERIENCE_DESCRIPTION_MORE: &str = "p.show-more-less-text__text--more";
-    pub const EXPERIENCE_DESCRIPTION_LESS: &str = "p.show-more-less-text__text--less";
-    pub const EXPERIENCE_DATE_TIME: &str = "span.date-range time";
-    pub const EXPERIENCE_DURATION: &str = "span.date-range__duration";
-    pub const EDUCATION_ITEM: &str = "li.profile-section-card";
-    pub const EDUCATION_ORGANIZATION: &str = "h3";
-    pub const EDUCATION_LINK: &str = "a";
-    pub const EDUCATION_DETAILS: &str = "h4 > p:first-child";
-    pub const EDUCATION_DESCRIPTION: &str = "div.text-color-text-low-emphasis";
-    pub const EDUCATION_DATE_TIME: &str = "span.date-range time";
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeopleSelectors {
+    pub top_card_layout: SelectorSpec,
+    pub name: SelectorSpec,
+    pub description: SelectorSpec,
+    pub location: SelectorSpec,
+    pub followers: SelectorSpec,
+    pub connections: SelectorSpec,
+    pub subline_item: SelectorSpec,
+    pub projects_items: SelectorSpec,
+    pub project_title: SelectorSpec,
+    pub project_description: SelectorSpec,
+    pub project_link: SelectorSpec,
+    pub languages_items: SelectorSpec,
+    pub language_name: SelectorSpec,
+    pub language_proficiency: SelectorSpec,
+    pub activities_items: SelectorSpec,
+    pub activity_title: SelectorSpec,
+    pub activity_link: SelectorSpec,
+    pub experience_item: SelectorSpec,
+    pub experience_education_company_logo: SelectorSpec,
+    pub experience_title: SelectorSpec,
+    pub experience_location: SelectorSpec,
+    pub experience_description_more: SelectorSpec,
+    pub experience_description_less: SelectorSpec,
+    pub experience_date_time: SelectorSpec,
+    pub experience_duration: SelectorSpec,
+    pub education_item: SelectorSpec,
+    pub education_organization: SelectorSpec,
+    pub education_link: SelectorSpec,
+    pub education_details: SelectorSpec,
+    pub education_description: SelectorSpec,
+    pub education_date_time: SelectorSpec,
+}
+
+impl Default for PeopleSelectors {
+    fn default() -> Self {
+        Self {
+            top_card_layout: ["section.top-card-layout", "section.pv-top-card"].into(),
+            name: ["h1.top-card-layout__title", "h1.text-heading-xlarge"].into(),
+            description: "span.top-card-link__description".into(),
+            location: ".profile-info-subheader span:first-child".into(),
+            followers: ".not-first-middot span:first-child".into(),
+            connections: ".not-first-middot span:last-child".into(),
+            subline_item: "span.top-card__subline-item".into(),
+            projects_items: "section[data-section='projects'] ul > li.personal-project".into(),
+            project_title: "h3".into(),
+            project_description: "p.show-more-less-text__text--less".into(),
+            project_link: "h3 a".into(),
+            languages_items: SelectorSpec::from(
+                "section[data-section='languages'] ul > li.profile-section-card"
+            ),
+            language_name: "h3".into(),
+            language_proficiency: "h4".into(),
+            activities_items: SelectorSpec::from(
+                "section[data-section='posts'] ul[data-test-id='activities__list'] > li"
+            ),
+            activity_title: "h3.base-main-card__title".into(),
+            activity_link: "a.base-card__full-link".into(),
+            experience_item: ["li.profile-section-card", "li.experience-item"].into(),
+            experience_education_company_logo: SelectorSpec::from(
+                "li.profile-section-card img.profile-section-card__image"
+            ),
+            experience_title: "h4 > p:first-child".into(),
+            experience_location: "div.text-color-text-low-emphasis".into(),
+            experience_description_more: "p.show-more-less-text__text--more".into(),
+            experience_description_less: "p.show-more-less-text__text--less".into(),
+            experience_date_time: "span.date-range time".into(),
+            experience_duration: "span.date-range__duration".into(),
+            education_item: ["li.profile-section-card", "li.education__list-item"].into(),
+            education_organization: "h3".into(),
+            education_link: "a".into(),
+            education_details: "h4 > p:first-child".into(),
+            education_description: "div.text-color-text-low-emphasis".into(),
+            education_date_time: "span.date-range time".into(),
+        }
+    }
 }
@@ -0,0 +1,230 @@
+use std::fs;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::SystemTime;
+use crate::config::Config;
+use crate::utils::selector_utils::{ SelectorHits, SelectorSpec };
+use tracing::{ info, warn };
+use std::path::{ Path, PathBuf };
+use anyhow::{ bail, Context, Result };
+use serde::{ Deserialize, Serialize };
+use crate::selectors::{ CompanySelectors, CompiledSelectors, JobSelectors, PeopleSelectors };
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SelectorConfig {
+    pub people: PeopleSelectors,
+    pub company: CompanySelectors,
+    pub jobs: JobSelectors,
+}
+
+impl SelectorConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs
+            ::read_to_string(path)
+            .with_context(|| format!("Failed to read selectors file {}", path.display()))?;
+
+        let selectors: Self = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") =>
+                toml
+                    ::from_str(&content)
+                    .with_context(|| format!("Invalid selectors file {}", path.display()))?,
+            Some("json") =>
+                serde_json
+                    ::from_str(&content)
+                    .with_context(|| format!("Invalid selectors file {}", path.display()))?,
+            _ => bail!("Selectors file {} must have a .toml or .json extension", path.display()),
+        };
+
+        selectors
+            .validate()
+            .with_context(|| format!("Invalid selectors in {}", path.display()))?;
+        Ok(selectors)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let value = serde_json::to_value(self)?;
+        let mut errors = Vec::new();
+
+        for (section, fields) in value.as_object().into_iter().flatten() {
+            for (field, spec) in fields.as_object().into_iter().flatten() {
+                let spec: SelectorSpec = serde_json::from_value(spec.clone())?;
+                if let Err(e) = spec.compile() {
+                    errors.push(format!("{section}.{field} = {e}"));
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            bail!("{}", errors.join("\n"));
+        }
+        Ok(())
+    }
+}
+
+struct LoadedSelectors {
+    modified: Option<SystemTime>,
+    selectors: Arc<SelectorConfig>,
+    compiled: Arc<CompiledSelectors>,
+}
+
+pub struct SelectorStore {
+    path: Option<PathBuf>,
+    watch: bool,
+    loaded: RwLock<LoadedSelectors>,
+    hits: Arc<SelectorHits>,
+}
+
+impl Default for SelectorStore {
+    fn default() -> Self {
+        let hits = Arc::new(SelectorHits::default());
+        let selectors = SelectorConfig::default();
+        let compiled = CompiledSelectors::new(&selectors, hits.clone())
+            .expect("Invalid built-in selectors");
+        Self {
+            path: None,
+            watch: false,
+            loaded: RwLock::new(LoadedSelectors {
+                modified: None,
+                selectors: Arc::new(selectors),
+                compiled: Arc::new(compiled),
+            }),
+            hits,
+        }
+    }
+}
+
+impl SelectorStore {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let Some(path) = &config.selectors_file else {
+            return Ok(Self::default());
+        };
+
+        let path = PathBuf::from(path);
+        let modified = Self::modified(&path);
+        let selectors = SelectorConfig::load(&path)?;
+        let hits = Arc::new(SelectorHits::default());
+        let compiled = CompiledSelectors::new(&selectors, hits.clone())?;
+        info!("Loaded selectors from {}", path.display());
+
+        Ok(Self {
+            path: Some(path),
+            watch: config.watch_selectors,
+            loaded: RwLock::new(LoadedSelectors {
+                modified,
+                selectors: Arc::new(selectors),
+                compiled: Arc::new(compiled),
+            }),
+            hits,
+        })
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    pub fn current(&self) -> Arc<SelectorConfig> {
+        if self.watch {
+            self.reload_if_changed();
+        }
+        self.loaded.read().unwrap().selectors.clone()
+    }
+
+    pub fn compiled(&self) -> Arc<CompiledSelectors> {
+        if self.watch {
+            self.reload_if_changed();
+        }
+        self.loaded.read().unwrap().compiled.clone()
+    }
+
+    pub fn hits(&self) -> Arc<SelectorHits> {
+        self.hits.clone()
+    }
+
+    fn reload_if_changed(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let modified = Self::modified(path);
+        if modified.is_none() || modified == self.loaded.read().unwrap().modified {
+            return;
+        }
+
+        let mut loaded = self.loaded.write().unwrap();
+        if modified == loaded.modified {
+            return;
+        }
+        loaded.modified = modified;
+
+        let reloaded = SelectorConfig::load(path).and_then(|selectors| {
+            let compiled = CompiledSelectors::new(&selectors, self.hits.clone())?;
+            Ok((selectors, compiled))
+        });
+        match reloaded {
+            Ok((selectors, compiled)) => {
+                info!("Reloaded selectors from {}", path.display());
+                loaded.selectors = Arc::new(selectors);
+                loaded.compiled = Arc::new(compiled);
+            }
+            Err(e) => warn!("Keeping previous selectors: {:#}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use std::fs::OpenOptions;
+
+    fn write(path: &Path, content: &str, modified: SystemTime) {
+        fs::write(path, content).unwrap();
+        OpenOptions::new().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn compiled_selectors_are_reused_until_the_file_changes() {
+        let file_name = format!("in-scraper-selectors-{}.toml", std::process::id());
+        let path = std::env::temp_dir().join(file_name);
+        let start = SystemTime::now() - Duration::from_secs(60);
+        write(&path, "[jobs]\nitem = \"li\"\n", start);
+
+        let config = Config {
+            selectors_file: Some(path.display().to_string()),
+            watch_selectors: true,
+            ..Config::default()
+        };
+        let store = SelectorStore::from_config(&config).unwrap();
+        let first = store.compiled();
+        assert!(Arc::ptr_eq(&first, &store.compiled()));
+
+        write(&path, "[jobs]\nitem = \"li.job\"\n", start + Duration::from_secs(10));
+        let reloaded = store.compiled();
+        assert!(!Arc::ptr_eq(&first, &reloaded));
+        assert_eq!(store.current().jobs.item, "li.job".into());
+
+        write(&path, "[jobs]\nitem = \"li[\"\n", start + Duration::from_secs(20));
+        let kept = store.compiled();
+        fs::remove_file(&path).unwrap();
+        assert!(Arc::ptr_eq(&reloaded, &kept));
+    }
+
+    #[test]
+    fn name_chains_ignore_headings_outside_the_top_card() {
+        let compiled = SelectorStore::default().compiled();
+        let authwall = scraper::Html::parse_document(
+            "<main><h1>Sign in</h1><form class=\"authwall-sign-in-form\"></form></main>"
+        );
+        assert!(compiled.company.name.first(authwall.root_element()).is_none());
+        assert!(compiled.people.name.first(authwall.root_element()).is_none());
+
+        let company = scraper::Html::parse_document(
+            "<section class=\"top-card-layout\"><div><h1>Acme</h1></div></section>"
+        );
+        let name = compiled.company.name.first(company.root_element()).unwrap();
+        assert_eq!(name.text().collect::<String>(), "Acme");
+    }
+}
@@ -0,0 +1,342 @@
+use std::fs;
+use url::Url;
+use std::sync::Arc;
+use std::path::Path;
+use chrono::{ DateTime, Utc };
+use crate::config::Config;
+use crate::engine::Stats;
+use anyhow::{ bail, Result };
+use crate::middleware::Downloader;
+use crate::selectors::SelectorStore;
+use std::io::{ self, BufRead, Write };
+use scraper::{ ElementRef, Html, Selector };
+use crate::utils::{ HttpCache, HttpClient };
+use crate::engine::{ ArchivedItem, ResponseArchive };
+use crate::spiders::{ CompanyProfileSpider, JobsSpider, PeopleProfileSpider, Request, Spider };
+
+const SHELL_NAME: &str = "shell";
+const MAX_TEXT_LEN: usize = 200;
+
+const HELP: &str = "\
+Commands:
+  <selector>               Show text and attributes of elements matching a CSS selector
+  text <selector>          Show only the text of matching elements
+  attr <name> <selector>   Show one attribute of matching elements
+  html <selector>          Show the outer HTML of matching elements
+  count <selector>         Count matching elements
+  parse [spider]           Run company-profile, jobs or people-profile parse on the page
+  meta <key>=<value>       Set request meta used by parse
+  load <url-or-file>       Load another page
+  info                     Show the loaded page
+  help                     Show this help
+  quit                     Leave the shell";
+
+struct Page {
+    request: Request,
+    status: u16,
+    body: String,
+    response_id: Option<String>,
+    crawled_at: DateTime<Utc>,
+}
+
+pub struct Shell {
+    config: Arc<Config>,
+    spider: Option<String>,
+    selectors: Arc<SelectorStore>,
+    downloader: Downloader,
+    page: Option<Page>,
+}
+
+impl Shell {
+    pub fn new(config: Arc<Config>, spider: Option<String>) -> Result<Self> {
+        let http_client = HttpClient::new(config.clone())?;
+        let downloader = Downloader::from_config(
+            config.clone(),
+            http_client,
+            Arc::new(Stats::new())
+        )?;
+        let selectors = Arc::new(SelectorStore::from_config(&config)?);
+        Ok(Self { config, spider, selectors, downloader, page: None })
+    }
+
+    pub async fn load(&mut self, target: &str) -> Result<()> {
+        let path = Path::new(target);
+        let page = if path.is_file() {
+            Self::load_file(path)?
+        } else if let Ok(url) = Url::parse(target) {
+            let request = Request::new(target.to_string());
+            let response = self.downloader.fetch(&request, self.cache_namespace(&url)).await?;
+            let (status, body) = (response.status, response.body);
+            Page { request, status, body, response_id: None, crawled_at: Utc::now() }
+        } else {
+            bail!("{target} is neither a file nor a URL");
+        };
+
+        println!("Loaded {} ({} bytes, status {})", page.request.url, page.body.len(), page.status);
+        self.page = Some(page);
+        Ok(())
+    }
+
+    fn cache_namespace(&self, url: &Url) -> &'static str {
+        let spider = self.spider.as_deref().or_else(|| {
+            let path = url.path();
+            if path.starts_with("/in/") {
+                Some("people-profile")
+            } else if path.starts_with("/company/") {
+                Some("company-profile")
+            } else if path.starts_with("/jobs") {
+                Some("jobs")
+            } else {
+                None
+            }
+        });
+
+        match spider {
+            Some("company-profile") => CompanyProfileSpider::NAME,
+            Some("jobs") => JobsSpider::NAME,
+            Some("people-profile") => PeopleProfileSpider::NAME,
+            _ => SHELL_NAME,
+        }
+    }
+
+    fn load_file(path: &Path) -> Result<Page> {
+        let path = fs::canonicalize(path)?;
+
+        if let Some(entry) = HttpCache::read_file(&path) {
+            return Ok(Page {
+                request: Request::new(entry.request_url),
+                status: entry.response.status,
+                body: entry.response.body,
+                response_id: None,
+                crawled_at: entry.cached_at,
+            });
+        }
+
+        let body = String::from_utf8_lossy(&fs::read(&path)?).to_string();
+        Ok(match ResponseArchive::load_record(&path) {
+            Some(record) =>
+                Page {
+                    request: record.request,
+                    status: record.status,
+                    body,
+                    response_id: Some(record.id),
+                    crawled_at: record.archived_at,
+                },
+            None =>
+                Page {
+                    request: Request::from_file(&path),
+                    status: 200,
+                    body,
+                    response_id: None,
+                    crawled_at: Utc::now(),
+                },
+        })
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        println!("{HELP}");
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+
+        loop {
+            print!("> ");
+            io::stdout().flush()?;
+
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let argument = argument.trim();
+            let result = match command {
+                "quit" | "exit" => {
+                    break;
+                }
+                "help" => {
+                    println!("{HELP}");
+                    Ok(())
+                }
+                "info" => self.info(),
+                "load" => self.load(argument).await,
+                "meta" => self.set_meta(argument),
+                "parse" => self.parse(argument).await,
+                "text" => self.select(argument, |element| println!("{}", Self::text(element))),
+                "html" => self.select(argument, |element| println!("{}", element.html())),
+                "count" => self.count(argument),
+                "attr" => {
+                    match argument.split_once(char::is_whitespace) {
+                        Some((name, selector)) =>
+                            self.select(selector.trim(), |element| {
+                                println!("{}", element.value().attr(name).unwrap_or("<missing>"));
+                            }),
+                        None => Err(anyhow::anyhow!("Usage: attr <name> <selector>")),
+                    }
+                }
+                _ => self.select(line, Self::describe),
+            };
+
+            if let Err(e) = result {
+                println!("Error: {e:#}");
+            }
+        }
+        Ok(())
+    }
+
+    fn page(&self) -> Result<&Page> {
+        self.page.as_ref().ok_or_else(|| anyhow::anyhow!("No page loaded, use load <url-or-file>"))
+    }
+
+    fn info(&self) -> Result<()> {
+        let page = self.page()?;
+        println!("URL: {}", page.request.url);
+        println!("Status: {}", page.status);
+        println!("Size: {} bytes", page.body.len());
+        if let Some(response_id) = &page.response_id {
+            println!("Response ID: {response_id}");
+        }
+        println!("Meta: {:?}", page.request.meta);
+        Ok(())
+    }
+
+    fn set_meta(&mut self, argument: &str) -> Result<()> {
+        let Some((key, value)) = argument.split_once('=') else {
+            bail!("Usage: meta <key>=<value>");
+        };
+        let page = self.page.as_mut().ok_or_else(|| anyhow::anyhow!("No page loaded"))?;
+        page.request.meta.insert(key.trim().to_string(), value.trim().to_string());
+        Ok(())
+    }
+
+    fn compile(selector: &str) -> Result<Selector> {
+        if selector.is_empty() {
+            bail!("Missing selector");
+        }
+        Selector::parse(selector).map_err(|e| anyhow::anyhow!("Invalid selector {selector:?}: {e}"))
+    }
+
+    fn select(&self, selector: &str, show: impl Fn(ElementRef)) -> Result<()> {
+        let selector = Self::compile(selector)?;
+        let document = Html::parse_document(&self.page()?.body);
+
+        let mut count = 0;
+        for element in document.select(&selector) {
+            print!("[{count}] ");
+            show(element);
+            count += 1;
+        }
+        println!("{count} matches");
+        Ok(())
+    }
+
+    fn count(&self, selector: &str) -> Result<()> {
+        let selector = Self::compile(selector)?;
+        let document = Html::parse_document(&self.page()?.body);
+        println!("{} matches", document.select(&selector).count());
+        Ok(())
+    }
+
+    fn text(element: ElementRef) -> String {
+        let text = element.text().collect::<Vec<_>>().join(" ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.chars().count() > MAX_TEXT_LEN {
+            format!("{}...", text.chars().take(MAX_TEXT_LEN).collect::<String>())
+        } else {
+            text
+        }
+    }
+
+    fn describe(element: ElementRef) {
+        let attributes: Vec<String> = element
+            .value()
+            .attrs()
+            .map(|(name, value)| format!("{name}={value:?}"))
+            .collect();
+        if attributes.is_empty() {
+            println!("<{}>", element.value().name());
+        } else {
+            println!("<{} {}>", element.value().name(), attributes.join(" "));
+        }
+        println!("    {}", Self::text(element));
+    }
+
+    async fn parse(&self, spider: &str) -> Result<()> {
+        let page = self.page()?;
+        let spider = match (spider, &self.spider) {
+            ("", Some(selected)) => selected.as_str(),
+            _ => spider,
+        };
+        let count = match spider {
+            "company-profile" => {
+                let spider = CompanyProfileSpider::new(self.config.clone(), Vec::new());
+                let spider = spider.with_selectors(self.selectors.clone());
+                Self::run_parse(&spider, page).await?
+            }
+            "jobs" => {
+                let spider = JobsSpider::new(self.config.clone(), String::new(), String::new());
+                let spider = spider.with_selectors(self.selectors.clone());
+                Self::run_parse(&spider, page).await?
+            }
+            "people-profile" => {
+                let spider = PeopleProfileSpider::new(self.config.clone(), Vec::new());
+                let spider = spider.with_selectors(self.selectors.clone());
+                Self::run_parse(&spider, page).await?
+            }
+            _ => bail!("Unknown spider {spider:?}, expected company-profile, jobs or people-profile"),
+        };
+        println!("{count} items");
+        Ok(())
+    }
+
+    async fn run_parse<S: Spider>(spider: &S, page: &Page) -> Result<usize> {
+        let mut request = page.request.clone();
+        if let Some(path) = Url::parse(&request.url).ok().and_then(|url| url.to_file_path().ok()) {
+            for (key, value) in spider.synthetic_request(&path).meta {
+                request.meta.entry(key).or_insert(value);
+            }
+        }
+
+        let (items, requests) = spider.parse(page.body.clone(), &request).await?;
+        let count = items.len();
+        for item in items {
+            let item = ArchivedItem::to_value(item, page.response_id.clone(), page.crawled_at)?;
+            println!("{}", serde_json::to_string_pretty(&item)?);
+        }
+
+        if !requests.is_empty() {
+            println!("{} follow-up requests not fetched", requests.len());
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespace(spider: Option<&str>, url: &str) -> &'static str {
+        let shell = Shell::new(Arc::new(Config::default()), spider.map(String::from)).unwrap();
+        shell.cache_namespace(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn cache_namespace_follows_the_url() {
+        let people = namespace(None, "https://www.linkedin.com/in/someone/");
+        let company = namespace(None, "https://www.linkedin.com/company/acme/");
+        let jobs = namespace(None, "https://www.linkedin.com/jobs-guest/jobs/api/search");
+        assert_eq!(people, PeopleProfileSpider::NAME);
+        assert_eq!(company, CompanyProfileSpider::NAME);
+        assert_eq!(jobs, JobsSpider::NAME);
+        assert_eq!(namespace(None, "https://example.com/"), SHELL_NAME);
+    }
+
+    #[test]
+    fn selected_spider_overrides_the_url() {
+        let url = "https://www.linkedin.com/in/someone/";
+        assert_eq!(namespace(Some("company-profile"), url), CompanyProfileSpider::NAME);
+    }
+}
@@ -1,33 +1,144 @@
+use url::Url;
+use std::path::Path;
 use anyhow::Result;
 use std::sync::Arc;
-use serde::Serialize;
+use sha1::{ Digest, Sha1 };
 use crate::config::Config;
 use async_trait::async_trait;
 use crate::utils::HttpClient;
+use crate::utils::selector_utils::SelectorHits;
+use std::collections::HashMap;
+use schemars::JsonSchema;
+use serde::{ Deserialize, Serialize };
+use crate::middleware::DownloaderMiddleware;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Request {
     pub url: String,
-    pub meta: std::collections::HashMap<String, String>,
+    pub meta: HashMap<String, String>,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    #[serde(default)]
+    pub dont_filter: bool,
 }
 
 impl Request {
     pub fn new(url: String) -> Self {
         Self {
             url,
-            meta: std::collections::HashMap::new(),
+            meta: HashMap::new(),
+            headers: Vec::new(),
+            dont_filter: false,
         }
     }
 
+    pub fn from_file(path: &Path) -> Self {
+        let url = Url::from_file_path(path)
+            .map(String::from)
+            .unwrap_or_else(|_| path.display().to_string());
+        Self::new(url)
+    }
+
     pub fn with_meta(mut self, key: String, value: String) -> Self {
         self.meta.insert(key, value);
         self
     }
+
+    pub fn with_header(mut self, name: String, value: String) -> Self {
+        self.set_header(&name, value);
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_header(&mut self, name: &str, value: String) {
+        match self.headers.iter_mut().find(|(existing, _)| existing.eq_ignore_ascii_case(name)) {
+            Some(header) => {
+                header.1 = value;
+            }
+            None => self.headers.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn set_default_header(&mut self, name: &str, value: String) {
+        if self.header(name).is_none() {
+            self.headers.push((name.to_string(), value));
+        }
+    }
+
+    pub fn with_dont_filter(mut self, dont_filter: bool) -> Self {
+        self.dont_filter = dont_filter;
+        self
+    }
+
+    pub fn canonical_url(&self) -> String {
+        let Ok(mut url) = Url::parse(self.url.trim()) else {
+            return self.url.trim().to_string();
+        };
+
+        url.set_fragment(None);
+
+        let mut query: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+
+        if query.is_empty() {
+            url.set_query(None);
+        } else {
+            query.sort();
+            url.query_pairs_mut().clear().extend_pairs(query);
+        }
+
+        url.to_string()
+    }
+
+    pub fn fingerprint(&self, meta_keys: &[String]) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(self.canonical_url().as_bytes());
+
+        let mut keys: Vec<&String> = meta_keys.iter().collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            hasher.update(b"\n");
+            hasher.update(key.as_bytes());
+            hasher.update(b"=");
+            if let Some(value) = self.meta.get(key) {
+                hasher.update(value.as_bytes());
+            }
+        }
+
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub url: String,
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(skip)]
+    pub archive_id: Option<String>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
 }
 
 #[async_trait]
 pub trait Spider: Send + Sync + Clone {
-    type Item: Serialize + Send;
+    type Item: Serialize + JsonSchema + Send + 'static;
 
     fn name(&self) -> &str;
 
@@ -41,11 +152,80 @@ pub trait Spider: Send + Sync + Clone {
         request: &Request
     ) -> Result<(Vec<Self::Item>, Vec<Request>)>;
 
-    async fn execute_request(&self, request: Request) -> Result<(Vec<Self::Item>, Vec<Request>)> {
-        let http_client = self.get_http_client();
-        let response = http_client.get_text(&request.url).await?;
-        self.parse(response, &request).await
+    fn get_http_client(&self) -> &HttpClient;
+
+    fn downloader_middlewares(&self) -> Vec<Box<dyn DownloaderMiddleware>> {
+        Vec::new()
     }
 
-    fn get_http_client(&self) -> &HttpClient;
+    fn synthetic_request(&self, path: &Path) -> Request {
+        Request::from_file(path)
+    }
+
+    fn selector_hits(&self) -> Option<Arc<SelectorHits>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str) -> Request {
+        Request::new(url.to_string())
+    }
+
+    #[test]
+    fn canonical_url_sorts_query_and_drops_fragment() {
+        let url = request("https://www.linkedin.com/jobs/search?start=25&keywords=rust#results");
+        let expected = "https://www.linkedin.com/jobs/search?keywords=rust&start=25";
+        assert_eq!(url.canonical_url(), expected);
+    }
+
+    #[test]
+    fn canonical_url_drops_empty_query() {
+        let url = request(" https://www.linkedin.com/in/someone? ");
+        assert_eq!(url.canonical_url(), "https://www.linkedin.com/in/someone");
+    }
+
+    #[test]
+    fn canonical_url_keeps_unparseable_urls() {
+        assert_eq!(request(" not a url ").canonical_url(), "not a url");
+    }
+
+    #[test]
+    fn fingerprint_ignores_query_order_and_fragment() {
+        let first = request("https://example.com/a?b=2&a=1#top");
+        let second = request("https://example.com/a?a=1&b=2");
+        assert_eq!(first.fingerprint(&[]), second.fingerprint(&[]));
+        assert_ne!(first.fingerprint(&[]), request("https://example.com/a?a=1").fingerprint(&[]));
+    }
+
+    #[test]
+    fn fingerprint_includes_only_selected_meta_keys() {
+        let plain = request("https://example.com/a");
+        let with_meta = plain
+            .clone()
+            .with_meta("session".to_string(), "one".to_string())
+            .with_meta("retry_times".to_string(), "2".to_string());
+
+        assert_eq!(plain.fingerprint(&[]), with_meta.fingerprint(&[]));
+
+        let keys = ["session".to_string()];
+        assert_ne!(plain.fingerprint(&keys), with_meta.fingerprint(&keys));
+        let other_session = with_meta.clone().with_meta("session".to_string(), "two".to_string());
+        assert_ne!(with_meta.fingerprint(&keys), other_session.fingerprint(&keys));
+    }
+
+    #[test]
+    fn fingerprint_ignores_meta_key_order_and_duplicates() {
+        let request = request("https://example.com/a")
+            .with_meta("a".to_string(), "1".to_string())
+            .with_meta("b".to_string(), "2".to_string());
+        let keys = ["b".to_string(), "a".to_string(), "b".to_string()];
+        assert_eq!(
+            request.fingerprint(&keys),
+            request.fingerprint(&["a".to_string(), "b".to_string()])
+        );
+    }
 }
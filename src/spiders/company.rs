@@ -5,41 +5,50 @@ use async_trait::async_trait;
 use htmlentity::entity::{ decode, ICodedDataTrait };
 use crate::{
     config::Config,
-    items::CompanyProfile,
+    error::ScrapeError,
+    items::{ CompanyProfile, SCHEMA_VERSION },
+    selectors::SelectorStore,
     spiders::{ Spider, Request },
-    utils::{ selector_utils::parse_selector, HttpClient },
+    utils::{ selector_utils::{ SelectorChain, SelectorHits }, HttpClient },
 };
-use scraper::{ Html, Selector, ElementRef };
+use scraper::{ Html, ElementRef };
 
 #[derive(Clone)]
 pub struct CompanyProfileSpider {
     config: Arc<Config>,
     http_client: HttpClient,
     company_pages: Vec<String>,
-    name_selector: Selector,
-    summary_selector: Selector,
-    details_selector: Selector,
-    text_selector: Selector,
+    selectors: Arc<SelectorStore>,
 }
 
 impl CompanyProfileSpider {
+    pub const NAME: &str = "linkedin_company_profile";
+
     pub fn new(config: Arc<Config>, company_pages: Vec<String>) -> Self {
         let http_client = HttpClient::new(config.clone()).expect("Failed to create HTTP client");
         Self {
             config,
             http_client,
             company_pages,
-            name_selector: parse_selector(crate::selectors::CompanySelectors::NAME),
-            text_selector: parse_selector(crate::selectors::CompanySelectors::TEXT_MD),
-            summary_selector: parse_selector(crate::selectors::CompanySelectors::SUMMARY),
-            details_selector: parse_selector(crate::selectors::CompanySelectors::DETAILS),
+            selectors: Arc::new(SelectorStore::default()),
         }
     }
 
-    fn extract_detail(&self, details: &[ElementRef], index: usize) -> Option<String> {
+    pub fn with_selectors(mut self, selectors: Arc<SelectorStore>) -> Self {
+        self.selectors = selectors;
+        self
+    }
+
+    fn extract_detail(
+        &self,
+        details: &[ElementRef],
+        text_selector: &SelectorChain,
+        index: usize
+    ) -> Option<String> {
         details.get(index).and_then(|detail_element| {
-            let texts: Vec<String> = detail_element
-                .select(&self.text_selector)
+            let texts: Vec<String> = text_selector
+                .all(*detail_element)
+                .into_iter()
                 .map(|el| el.text().collect::<String>().trim().to_string())
                 .collect();
 
@@ -53,7 +62,7 @@ impl Spider for CompanyProfileSpider {
     type Item = CompanyProfile;
 
     fn name(&self) -> &str {
-        "linkedin_company_profile"
+        Self::NAME
     }
 
     fn get_config(&self) -> &Arc<Config> {
@@ -64,6 +73,10 @@ impl Spider for CompanyProfileSpider {
         &self.http_client
     }
 
+    fn selector_hits(&self) -> Option<Arc<SelectorHits>> {
+        Some(self.selectors.hits())
+    }
+
     async fn start_requests(&self) -> Vec<Request> {
         self.company_pages
             .iter()
@@ -85,29 +98,34 @@ impl Spider for CompanyProfileSpider {
             .unwrap_or(0);
         info!("Parsing company {} of {}", company_index + 1, self.company_pages.len());
 
+        let compiled = self.selectors.compiled();
+        let selectors = &compiled.company;
+
         let decoded = decode(response.as_bytes());
         let document = Html::parse_document(&decoded.to_string().unwrap());
 
-        let name = document
-            .select(&self.name_selector)
-            .next()
+        let Some(name) = selectors.name
+            .first(document.root_element())
             .map(|el| el.text().collect::<String>().trim().to_string())
-            .unwrap_or_else(|| "not-found".to_string());
+            .filter(|name| !name.is_empty()) else {
+            return Err(ScrapeError::EmptyPage { url: request.url.clone() }.into());
+        };
 
-        let summary = document
-            .select(&self.summary_selector)
-            .next()
+        let summary = selectors.summary
+            .first(document.root_element())
             .map(|el| el.text().collect::<String>().trim().to_string())
-            .unwrap_or_else(|| "not-found".to_string());
+            .filter(|summary| !summary.is_empty());
 
-        let details: Vec<_> = document.select(&self.details_selector).collect();
+        let details = selectors.details.all(document.root_element());
 
         let company = CompanyProfile {
+            schema_version: SCHEMA_VERSION,
+            url: request.url.clone(),
             name,
             summary,
-            industry: self.extract_detail(&details, 1),
-            size: self.extract_detail(&details, 2),
-            founded: self.extract_detail(&details, 5),
+            industry: self.extract_detail(&details, &selectors.text_md, 1),
+            size: self.extract_detail(&details, &selectors.text_md, 2),
+            founded: self.extract_detail(&details, &selectors.text_md, 5),
         };
 
         Ok((vec![company], vec![]))
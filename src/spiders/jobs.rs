@@ -4,13 +4,14 @@ use std::sync::Arc;
 use urlencoding::encode;
 use async_trait::async_trait;
 use std::collections::HashSet;
-use scraper::{ Html, Selector, ElementRef };
+use scraper::{ Html, ElementRef };
 use htmlentity::entity::{ decode, ICodedDataTrait };
 use crate::{
     config::Config,
-    items::JobListing,
+    items::{ JobListing, SCHEMA_VERSION },
+    selectors::SelectorStore,
     spiders::{ Spider, Request },
-    utils::{ selector_utils::parse_selector, HttpClient },
+    utils::{ selector_utils::{ SelectorChain, SelectorHits }, HttpClient },
 };
 
 #[derive(Clone)]
@@ -19,9 +20,12 @@ pub struct JobsSpider {
     http_client: HttpClient,
     keywords: String,
     location: String,
+    selectors: Arc<SelectorStore>,
 }
 
 impl JobsSpider {
+    pub const NAME: &str = "linkedin_jobs";
+
     pub fn new(config: Arc<Config>, keywords: String, location: String) -> Self {
         let http_client = HttpClient::new(config.clone()).expect("Failed to create HTTP client");
         Self {
@@ -29,9 +33,15 @@ impl JobsSpider {
             http_client,
             keywords,
             location,
+            selectors: Arc::new(SelectorStore::default()),
         }
     }
 
+    pub fn with_selectors(mut self, selectors: Arc<SelectorStore>) -> Self {
+        self.selectors = selectors;
+        self
+    }
+
     fn build_url(&self, start: usize) -> String {
         format!(
             "https://www.linkedin.com/jobs-guest/jobs/api/seeMoreJobPostings/search?keywords={}&location={}&start={}",
@@ -45,21 +55,18 @@ impl JobsSpider {
         if let Some(pos) = url.find('?') { &url[..pos] } else { url }
     }
 
-    fn extract_text(element: ElementRef, selector: &Selector) -> String {
-        element
-            .select(selector)
-            .next()
+    fn extract_text(element: ElementRef, selector: &SelectorChain) -> Option<String> {
+        selector
+            .first(element)
             .map(|el| el.text().collect::<String>().trim().to_string())
-            .unwrap_or_else(|| "not-found".to_string())
+            .filter(|text| !text.is_empty())
     }
 
-    fn extract_href(element: ElementRef, selector: &Selector) -> String {
-        element
-            .select(selector)
-            .next()
+    fn extract_href(element: ElementRef, selector: &SelectorChain) -> Option<String> {
+        selector
+            .first(element)
             .and_then(|el| el.value().attr("href"))
-            .unwrap_or("not-found")
-            .to_string()
+            .map(String::from)
     }
 }
 
@@ -68,7 +75,7 @@ impl Spider for JobsSpider {
     type Item = JobListing;
 
     fn name(&self) -> &str {
-        "linkedin_jobs"
+        Self::NAME
     }
 
     fn get_config(&self) -> &Arc<Config> {
@@ -79,6 +86,10 @@ impl Spider for JobsSpider {
         &self.http_client
     }
 
+    fn selector_hits(&self) -> Option<Arc<SelectorHits>> {
+        Some(self.selectors.hits())
+    }
+
     async fn start_requests(&self) -> Vec<Request> {
         vec![Request::new(self.build_url(0)).with_meta("start".to_string(), "0".to_string())]
     }
@@ -96,34 +107,34 @@ impl Spider for JobsSpider {
         let decoded = decode(response.as_bytes());
         let document = Html::parse_document(&decoded.to_string().unwrap());
 
-        let job_selector = parse_selector(crate::selectors::JobSelectors::ITEM);
-        let title_selector = parse_selector(crate::selectors::JobSelectors::TITLE);
-        let url_selector = parse_selector(crate::selectors::JobSelectors::URL);
-        let time_selector = parse_selector(crate::selectors::JobSelectors::TIME);
-        let company_name_selector = parse_selector(crate::selectors::JobSelectors::COMPANY_NAME);
-        let location_selector = parse_selector(crate::selectors::JobSelectors::LOCATION);
+        let compiled = self.selectors.compiled();
+        let selectors = &compiled.jobs;
 
         let mut items = Vec::new();
         let mut seen_urls = HashSet::new();
 
-        let jobs: Vec<_> = document.select(&job_selector).collect();
+        let jobs = selectors.item.all(document.root_element());
         info!("Jobs found on page: {}", jobs.len());
 
         for job in jobs.iter() {
-            let raw_url = Self::extract_href(*job, &url_selector);
+            let Some(raw_url) = Self::extract_href(*job, &selectors.url) else {
+                continue;
+            };
             let truncated_url = self.truncate_url_params(&raw_url).to_string();
 
-            if truncated_url == "not-found" || !seen_urls.insert(truncated_url.clone()) {
+            if !seen_urls.insert(truncated_url.clone()) {
                 continue;
             }
 
             items.push(JobListing {
+                schema_version: SCHEMA_VERSION,
                 job_detail_url: truncated_url,
-                job_title: Self::extract_text(*job, &title_selector),
-                job_listed: Self::extract_text(*job, &time_selector),
-                company_name: Self::extract_text(*job, &company_name_selector),
-                company_link: Self::extract_href(*job, &company_name_selector),
-                company_location: Self::extract_text(*job, &location_selector),
+                job_title: Self::extract_text(*job, &selectors.title),
+                job_listed: Self::extract_text(*job, &selectors.time),
+                job_listed_date: None,
+                company_name: Self::extract_text(*job, &selectors.company_name),
+                company_link: Self::extract_href(*job, &selectors.company_name),
+                company_location: Self::extract_text(*job, &selectors.location),
             });
         }
 
@@ -4,6 +4,6 @@ pub mod people;
 pub mod company;
 
 pub use jobs::JobsSpider;
-pub use base::{ Spider, Request };
+pub use base::{ Spider, Request, Response };
 pub use people::PeopleProfileSpider;
 pub use company::CompanyProfileSpider;
@@ -1,125 +1,51 @@
 use std::sync::Arc;
+use std::path::Path;
 use anyhow::Result;
 use async_trait::async_trait;
-use scraper::{ Html, Selector, ElementRef };
+use scraper::{ Html, ElementRef };
 use htmlentity::entity::{ decode, ICodedDataTrait };
 use crate::{
     config::Config,
+    error::ScrapeError,
     spiders::{ Request, Spider },
-    utils::{ selector_utils::parse_selector, HttpClient },
-    items::{ Activity, Education, Experience, Language, PersonProfile, Project },
+    selectors::{ PeopleChains, SelectorStore },
+    utils::{ selector_utils::{ SelectorChain, SelectorHits }, HttpClient },
+    items::{ Activity, Education, Experience, Language, PersonProfile, Project, SCHEMA_VERSION },
 };
 
-#[derive(Clone)]
-struct CompiledSelectors {
-    summary: Selector,
-    name: Selector,
-    description: Selector,
-    location: Selector,
-    followers: Selector,
-    connections: Selector,
-    subline_item: Selector,
-    exp_item: Selector,
-    exp_title: Selector,
-    exp_location: Selector,
-    exp_desc_more: Selector,
-    exp_desc_less: Selector,
-    exp_date_time: Selector,
-    exp_duration: Selector,
-    exp_company_logo: Selector,
-    edu_item: Selector,
-    edu_org: Selector,
-    edu_link: Selector,
-    edu_details: Selector,
-    edu_desc: Selector,
-    edu_date_time: Selector,
-    projects_items: Selector,
-    project_title: Selector,
-    project_description: Selector,
-    project_link: Selector,
-    languages_items: Selector,
-    language_name: Selector,
-    language_proficiency: Selector,
-    activities_items: Selector,
-    activity_title: Selector,
-    activity_link: Selector,
-}
-
-impl CompiledSelectors {
-    fn new() -> Self {
-        Self {
-            summary: parse_selector(crate::selectors::PeopleSelectors::TOP_CARD_LAYOUT),
-            name: parse_selector(crate::selectors::PeopleSelectors::NAME),
-            description: parse_selector(crate::selectors::PeopleSelectors::DESCRIPTION),
-            location: parse_selector(crate::selectors::PeopleSelectors::LOCATION),
-            followers: parse_selector(crate::selectors::PeopleSelectors::FOLLOWERS),
-            connections: parse_selector(crate::selectors::PeopleSelectors::CONNECTIONS),
-            subline_item: parse_selector(crate::selectors::PeopleSelectors::SUBLINE_ITEM),
-            exp_item: parse_selector(crate::selectors::PeopleSelectors::EXPERIENCE_ITEM),
-            exp_title: parse_selector(crate::selectors::PeopleSelectors::EXPERIENCE_TITLE),
-            exp_location: parse_selector(crate::selectors::PeopleSelectors::EXPERIENCE_LOCATION),
-            exp_desc_more: parse_selector(
-                crate::selectors::PeopleSelectors::EXPERIENCE_DESCRIPTION_MORE
-            ),
-            exp_desc_less: parse_selector(
-                crate::selectors::PeopleSelectors::EXPERIENCE_DESCRIPTION_LESS
-            ),
-            exp_date_time: parse_selector(crate::selectors::PeopleSelectors::EXPERIENCE_DATE_TIME),
-            exp_duration: parse_selector(crate::selectors::PeopleSelectors::EXPERIENCE_DURATION),
-            exp_company_logo: parse_selector(
-                crate::selectors::PeopleSelectors::EXPERIENCE_EDUCATION_COMPANY_LOGO
-            ),
-            edu_item: parse_selector(crate::selectors::PeopleSelectors::EDUCATION_ITEM),
-            edu_org: parse_selector(crate::selectors::PeopleSelectors::EDUCATION_ORGANIZATION),
-            edu_link: parse_selector(crate::selectors::PeopleSelectors::EDUCATION_LINK),
-            edu_details: parse_selector(crate::selectors::PeopleSelectors::EDUCATION_DETAILS),
-            edu_desc: parse_selector(crate::selectors::PeopleSelectors::EDUCATION_DESCRIPTION),
-            edu_date_time: parse_selector(crate::selectors::PeopleSelectors::EDUCATION_DATE_TIME),
-            projects_items: parse_selector(crate::selectors::PeopleSelectors::PROJECTS_ITEMS),
-            project_title: parse_selector(crate::selectors::PeopleSelectors::PROJECT_TITLE),
-            project_description: parse_selector(
-                crate::selectors::PeopleSelectors::PROJECT_DESCRIPTION
-            ),
-            project_link: parse_selector(crate::selectors::PeopleSelectors::PROJECT_LINK),
-            languages_items: parse_selector(crate::selectors::PeopleSelectors::LANGUAGES_ITEMS),
-            language_name: parse_selector(crate::selectors::PeopleSelectors::LANGUAGE_NAME),
-            language_proficiency: parse_selector(
-                crate::selectors::PeopleSelectors::LANGUAGE_PROFICIENCY
-            ),
-            activities_items: parse_selector(crate::selectors::PeopleSelectors::ACTIVITIES_ITEMS),
-            activity_title: parse_selector(crate::selectors::PeopleSelectors::ACTIVITY_TITLE),
-            activity_link: parse_selector(crate::selectors::PeopleSelectors::ACTIVITY_LINK),
-        }
-    }
-}
-
 #[derive(Clone)]
 pub struct PeopleProfileSpider {
     config: Arc<Config>,
     http_client: HttpClient,
     profiles: Vec<String>,
-    selectors: CompiledSelectors,
+    selectors: Arc<SelectorStore>,
 }
 
 impl PeopleProfileSpider {
+    pub const NAME: &str = "linkedin_people_profile";
+
     pub fn new(config: Arc<Config>, profiles: Vec<String>) -> Self {
         let http_client = HttpClient::new(config.clone()).expect("Failed to create HTTP client");
         Self {
             config,
             http_client,
             profiles,
-            selectors: CompiledSelectors::new(),
+            selectors: Arc::new(SelectorStore::default()),
         }
     }
 
+    pub fn with_selectors(mut self, selectors: Arc<SelectorStore>) -> Self {
+        self.selectors = selectors;
+        self
+    }
+
     fn build_url(&self, profile: &str) -> String {
         format!("https://linkedin.com/in/{profile}/")
     }
 
-    fn extract_text(element: ElementRef, selector: &Selector) -> Option<String> {
-        element
-            .select(selector)
-            .next()
+    fn extract_text(element: ElementRef, selector: &SelectorChain) -> Option<String> {
+        selector
+            .first(element)
             .map(|el| el.text().collect::<String>().trim().to_string())
             .filter(|text| !text.is_empty())
     }
@@ -136,67 +62,68 @@ impl PeopleProfileSpider {
         }
     }
 
-    fn extract_date_ranges(element: ElementRef, selector: &Selector) -> Vec<String> {
-        element
-            .select(selector)
+    fn extract_date_ranges(element: ElementRef, selector: &SelectorChain) -> Vec<String> {
+        selector
+            .all(element)
+            .into_iter()
             .map(|el| el.text().collect::<String>().trim().to_string())
             .filter(|text| !text.is_empty())
             .collect()
     }
 
-    fn parse_experience(&self, document: &Html) -> Vec<Experience> {
-        document
-            .select(&self.selectors.exp_item)
+    fn parse_experience(&self, document: &Html, selectors: &PeopleChains) -> Vec<Experience> {
+        selectors.exp_item
+            .all(document.root_element())
+            .into_iter()
             .map(|block| {
-                let date_ranges = Self::extract_date_ranges(block, &self.selectors.exp_date_time);
+                let date_ranges = Self::extract_date_ranges(block, &selectors.exp_date_time);
                 let (start_time, end_time) = Self::parse_date_range(&date_ranges);
 
                 Experience {
-                    organization_profile: block
-                        .select(&self.selectors.exp_title)
-                        .next()
+                    organization_profile: selectors.exp_title
+                        .first(block)
                         .and_then(|el| el.value().attr("href"))
                         .map(Self::truncate_url),
-                    location: Self::extract_text(block, &self.selectors.exp_location),
-                    description: Self::extract_text(block, &self.selectors.exp_desc_more).or_else(||
-                        Self::extract_text(block, &self.selectors.exp_desc_less)
+                    location: Self::extract_text(block, &selectors.exp_location),
+                    description: Self::extract_text(block, &selectors.exp_desc_more).or_else(||
+                        Self::extract_text(block, &selectors.exp_desc_less)
                     ),
-                    duration: Self::extract_text(block, &self.selectors.exp_duration),
+                    duration: Self::extract_text(block, &selectors.exp_duration),
+                    duration_months: None,
                     start_time,
                     end_time,
-                    logo: block
-                        .select(&self.selectors.exp_company_logo)
-                        .next()
+                    start_date: None,
+                    end_date: None,
+                    logo: selectors.exp_company_logo
+                        .first(block)
                         .and_then(|el| el.value().attr("src"))
                         .map(String::from),
-                    title: Self::extract_text(block, &self.selectors.exp_title),
+                    title: Self::extract_text(block, &selectors.exp_title),
                 }
             })
             .collect()
     }
 
-    fn parse_education(&self, document: &Html) -> Vec<Education> {
-        document
-            .select(&self.selectors.edu_item)
+    fn parse_education(&self, document: &Html, selectors: &PeopleChains) -> Vec<Education> {
+        selectors.edu_item
+            .all(document.root_element())
+            .into_iter()
             .map(|block| {
-                let course_details = block
-                    .select(&self.selectors.edu_details)
+                let course_details = selectors.edu_details
+                    .all(block)
+                    .into_iter()
                     .map(|el| el.text().collect::<String>().trim().to_string())
                     .filter(|text| !text.is_empty())
                     .collect::<Vec<_>>()
                     .join(" ");
 
-                let date_ranges = Self::extract_date_ranges(block, &self.selectors.edu_date_time);
+                let date_ranges = Self::extract_date_ranges(block, &selectors.edu_date_time);
                 let (start_time, end_time) = Self::parse_date_range(&date_ranges);
 
                 Education {
-                    organization: Self::extract_text(
-                        block,
-                        &self.selectors.edu_org
-                    ).unwrap_or_default(),
-                    organization_profile: block
-                        .select(&self.selectors.edu_link)
-                        .next()
+                    organization: Self::extract_text(block, &selectors.edu_org),
+                    organization_profile: selectors.edu_link
+                        .first(block)
                         .and_then(|el| el.value().attr("href"))
                         .map(Self::truncate_url),
                     course_details: if course_details.is_empty() {
@@ -204,47 +131,50 @@ impl PeopleProfileSpider {
                     } else {
                         Some(course_details)
                     },
-                    description: Self::extract_text(block, &self.selectors.edu_desc),
+                    description: Self::extract_text(block, &selectors.edu_desc),
                     start_time,
                     end_time,
+                    start_date: None,
+                    end_date: None,
                 }
             })
             .collect()
     }
 
-    fn parse_projects(&self, document: &Html) -> Vec<Project> {
-        document
-            .select(&self.selectors.projects_items)
+    fn parse_projects(&self, document: &Html, selectors: &PeopleChains) -> Vec<Project> {
+        selectors.projects_items
+            .all(document.root_element())
+            .into_iter()
             .map(|block| Project {
-                name: Self::extract_text(block, &self.selectors.project_title),
-                description: Self::extract_text(block, &self.selectors.project_description),
-                url: block
-                    .select(&self.selectors.project_link)
-                    .next()
+                name: Self::extract_text(block, &selectors.project_title),
+                description: Self::extract_text(block, &selectors.project_description),
+                url: selectors.project_link
+                    .first(block)
                     .and_then(|el| el.value().attr("href"))
                     .map(Self::truncate_url),
             })
             .collect()
     }
 
-    fn parse_languages(&self, document: &Html) -> Vec<Language> {
-        document
-            .select(&self.selectors.languages_items)
+    fn parse_languages(&self, document: &Html, selectors: &PeopleChains) -> Vec<Language> {
+        selectors.languages_items
+            .all(document.root_element())
+            .into_iter()
             .map(|block| Language {
-                name: Self::extract_text(block, &self.selectors.language_name),
-                proficiency: Self::extract_text(block, &self.selectors.language_proficiency),
+                name: Self::extract_text(block, &selectors.language_name),
+                proficiency: Self::extract_text(block, &selectors.language_proficiency),
             })
             .collect()
     }
 
-    fn parse_activities(&self, document: &Html) -> Vec<Activity> {
-        document
-            .select(&self.selectors.activities_items)
+    fn parse_activities(&self, document: &Html, selectors: &PeopleChains) -> Vec<Activity> {
+        selectors.activities_items
+            .all(document.root_element())
+            .into_iter()
             .map(|block| Activity {
-                title: Self::extract_text(block, &self.selectors.activity_title),
-                url: block
-                    .select(&self.selectors.activity_link)
-                    .next()
+                title: Self::extract_text(block, &selectors.activity_title),
+                url: selectors.activity_link
+                    .first(block)
                     .and_then(|el| el.value().attr("href"))
                     .map(Self::truncate_url),
             })
@@ -253,15 +183,17 @@ impl PeopleProfileSpider {
 
     fn extract_location_followers_connections(
         &self,
-        summary_box: ElementRef
+        summary_box: ElementRef,
+        selectors: &PeopleChains
     ) -> (Option<String>, Option<String>, Option<String>) {
-        let mut location = Self::extract_text(summary_box, &self.selectors.location);
-        let mut followers = Self::extract_text(summary_box, &self.selectors.followers);
-        let mut connections = Self::extract_text(summary_box, &self.selectors.connections);
+        let mut location = Self::extract_text(summary_box, &selectors.location);
+        let mut followers = Self::extract_text(summary_box, &selectors.followers);
+        let mut connections = Self::extract_text(summary_box, &selectors.connections);
 
         if location.is_none() || followers.is_none() || connections.is_none() {
-            let subline_items: Vec<String> = summary_box
-                .select(&self.selectors.subline_item)
+            let subline_items: Vec<String> = selectors.subline_item
+                .all(summary_box)
+                .into_iter()
                 .map(|el| el.text().collect::<String>().trim().to_string())
                 .filter(|text| !text.is_empty())
                 .collect();
@@ -286,7 +218,7 @@ impl Spider for PeopleProfileSpider {
     type Item = PersonProfile;
 
     fn name(&self) -> &str {
-        "linkedin_people_profile"
+        Self::NAME
     }
 
     fn get_config(&self) -> &Arc<Config> {
@@ -297,6 +229,10 @@ impl Spider for PeopleProfileSpider {
         &self.http_client
     }
 
+    fn selector_hits(&self) -> Option<Arc<SelectorHits>> {
+        Some(self.selectors.hits())
+    }
+
     async fn start_requests(&self) -> Vec<Request> {
         self.profiles
             .iter()
@@ -309,6 +245,17 @@ impl Spider for PeopleProfileSpider {
             .collect()
     }
 
+    fn synthetic_request(&self, path: &Path) -> Request {
+        let profile = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        Request::from_file(path)
+            .with_meta("linkedin_url".to_string(), self.build_url(&profile))
+            .with_meta("profile".to_string(), profile)
+    }
+
     async fn parse(
         &self,
         response: String,
@@ -323,47 +270,33 @@ impl Spider for PeopleProfileSpider {
             .cloned()
             .unwrap_or_else(|| "unknown".to_string());
 
+        let compiled = self.selectors.compiled();
+        let selectors = &compiled.people;
         let decoded = decode(response.as_bytes());
         let document = Html::parse_document(&decoded.to_string().unwrap());
 
-        // std::fs
-        //     ::write(
-        //         format!(
-        //             "{}.html",
-        //             url
-        //                 .strip_prefix("https://")
-        //                 .or_else(|| url.strip_prefix("http://"))
-        //                 .unwrap_or(&url)
-        //                 .replace("/", "_")
-        //         ),
-        //         document.html()
-        //     )
-        //     .unwrap();
-
-        let summary_box = document.select(&self.selectors.summary).next();
-        let (location, followers, connections) = if let Some(box_el) = summary_box {
-            self.extract_location_followers_connections(box_el)
-        } else {
-            (None, None, None)
+        let summary_box = selectors.summary.first(document.root_element());
+        let name = summary_box.and_then(|el| Self::extract_text(el, &selectors.name));
+        let (Some(summary_box), Some(name)) = (summary_box, name) else {
+            return Err(ScrapeError::EmptyPage { url: request.url.clone() }.into());
         };
+        let (location, followers, connections) =
+            self.extract_location_followers_connections(summary_box, selectors);
 
         let person = PersonProfile {
+            schema_version: SCHEMA_VERSION,
             profile,
             url,
-            name: summary_box
-                .and_then(|el| Self::extract_text(el, &self.selectors.name))
-                .unwrap_or_default(),
-            description: summary_box
-                .and_then(|el| Self::extract_text(el, &self.selectors.description))
-                .unwrap_or_default(),
-            experience: self.parse_experience(&document),
-            education: self.parse_education(&document),
+            name,
+            description: Self::extract_text(summary_box, &selectors.description),
+            experience: self.parse_experience(&document, selectors),
+            education: self.parse_education(&document, selectors),
             location,
             followers,
             connections,
-            projects: self.parse_projects(&document),
-            languages: self.parse_languages(&document),
-            activities: self.parse_activities(&document),
+            projects: self.parse_projects(&document, selectors),
+            languages: self.parse_languages(&document, selectors),
+            activities: self.parse_activities(&document, selectors),
         };
 
         Ok((vec![person], vec![]))
@@ -0,0 +1,116 @@
+use url::Url;
+use std::sync::LazyLock;
+use crate::error::ScrapeError;
+use crate::spiders::Response;
+use scraper::{ Html, Selector };
+
+const BLOCKED_STATUS: u16 = 999;
+
+const AUTHWALL_PATHS: [&str; 3] = ["/authwall", "/login", "/uas/login"];
+const AUTHWALL_FORMS: &str = "form.authwall-join-form, form.authwall-sign-in-form";
+
+static AUTHWALL_FORM: LazyLock<Selector> = LazyLock::new(|| {
+    Selector::parse(AUTHWALL_FORMS).expect("Invalid authwall selector")
+});
+
+const CHALLENGE_PATHS: [&str; 1] = ["/checkpoint/challenge"];
+const CHALLENGE_MARKERS: [&str; 2] = ["/checkpoint/challenge", "captcha-internal"];
+
+pub struct ResponseClassifier;
+
+impl ResponseClassifier {
+    pub fn classify(response: &Response) -> Option<ScrapeError> {
+        let url = response.url.clone();
+
+        if response.status == BLOCKED_STATUS {
+            return Some(ScrapeError::Blocked { url, status: response.status });
+        }
+
+        let parsed = Url::parse(&response.url).ok();
+        let path = parsed.as_ref().map(Url::path).unwrap_or_default();
+        let location = response
+            .header("location")
+            .and_then(|location| parsed.as_ref()?.join(location).ok());
+        let location = location.as_ref().map(Url::path).unwrap_or_default();
+        let is_authwall = |path: &str| AUTHWALL_PATHS.iter().any(|prefix| path.starts_with(prefix));
+
+        if
+            CHALLENGE_PATHS.iter().any(|prefix| path.starts_with(prefix)) ||
+            CHALLENGE_MARKERS.iter().any(|marker| response.body.contains(marker))
+        {
+            return Some(ScrapeError::Challenge { url });
+        }
+
+        if
+            is_authwall(path) ||
+            is_authwall(location) ||
+            Self::has_authwall_form(&response.body)
+        {
+            return Some(ScrapeError::AuthWall { url });
+        }
+
+        if response.body.trim().is_empty() {
+            return Some(ScrapeError::EmptyPage { url });
+        }
+
+        None
+    }
+
+    fn has_authwall_form(body: &str) -> bool {
+        body.contains("authwall-") &&
+            Html::parse_document(body).select(&AUTHWALL_FORM).next().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn response(url: &str, status: u16, body: &str) -> Response {
+        Response {
+            url: url.to_string(),
+            status,
+            headers: HashMap::new(),
+            body: body.to_string(),
+            proxy: None,
+            archive_id: None,
+        }
+    }
+
+    const PROFILE: &str = "https://www.linkedin.com/in/someone";
+
+    #[test]
+    fn guest_page_linking_to_the_authwall_is_not_an_authwall() {
+        let body = r#"<h1>Someone</h1><a href="/authwall?trk=public_profile">Join</a>"#;
+        assert!(ResponseClassifier::classify(&response(PROFILE, 200, body)).is_none());
+    }
+
+    #[test]
+    fn authwall_form_and_urls_are_authwalls() {
+        let form = r#"<form class="authwall-join-form" action="/signup"></form>"#;
+        let authwall = "https://www.linkedin.com/authwall?trk=bf&sessionRedirect=x";
+        let mut redirect = response(PROFILE, 302, "");
+        redirect.headers.insert("location".to_string(), "/authwall?trk=bf".to_string());
+
+        let responses = [response(PROFILE, 200, form), response(authwall, 200, "<html>"), redirect];
+        for response in responses {
+            let error = ResponseClassifier::classify(&response);
+            assert!(matches!(error, Some(ScrapeError::AuthWall { .. })), "{}", response.url);
+        }
+    }
+
+    #[test]
+    fn blocked_challenge_and_empty_pages() {
+        let blocked = ResponseClassifier::classify(&response(PROFILE, 999, ""));
+        assert!(matches!(blocked, Some(ScrapeError::Blocked { status: 999, .. })));
+
+        let challenge = "https://www.linkedin.com/checkpoint/challenge/abc";
+        let challenge = ResponseClassifier::classify(&response(challenge, 200, "<html>"));
+        assert!(matches!(challenge, Some(ScrapeError::Challenge { .. })));
+
+        let empty = ResponseClassifier::classify(&response(PROFILE, 200, "  \n"));
+        assert!(matches!(empty, Some(ScrapeError::EmptyPage { .. })));
+        assert!(!empty.unwrap().is_retryable());
+    }
+}
@@ -0,0 +1,185 @@
+use chrono::{ DateTime, Datelike, Duration, Months, NaiveDate, Utc };
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const PRESENT: [&str; 3] = ["present", "current", "now"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonthDate {
+    Month(i32, u32),
+    Year(i32),
+    Present,
+}
+
+impl MonthDate {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        if PRESENT.contains(&text.as_str()) {
+            return Some(Self::Present);
+        }
+
+        match text.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [year] => Self::year(year).map(Self::Year),
+            [month, year] => {
+                let month = match month.trim_end_matches('.') {
+                    "sept" => "sep",
+                    month if month.len() >= 3 => month,
+                    _ => {
+                        return None;
+                    }
+                };
+                let index = MONTHS.iter().position(|name| name.starts_with(month))?;
+                Some(Self::Month(Self::year(year)?, (index as u32) + 1))
+            }
+            _ => None,
+        }
+    }
+
+    fn year(text: &str) -> Option<i32> {
+        if text.len() != 4 || !text.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        text.parse().ok()
+    }
+
+    pub fn iso(&self) -> Option<String> {
+        match self {
+            Self::Month(year, month) => Some(format!("{year:04}-{month:02}")),
+            Self::Year(year) => Some(format!("{year:04}")),
+            Self::Present => None,
+        }
+    }
+
+    fn first_month(&self, today: NaiveDate) -> (i32, u32) {
+        match *self {
+            Self::Month(year, month) => (year, month),
+            Self::Year(year) => (year, 1),
+            Self::Present => (today.year(), today.month()),
+        }
+    }
+
+    fn last_month(&self, today: NaiveDate) -> (i32, u32) {
+        match *self {
+            Self::Month(year, month) => (year, month),
+            Self::Year(year) => (year, 12),
+            Self::Present => (today.year(), today.month()),
+        }
+    }
+}
+
+pub fn months_between(start: MonthDate, end: MonthDate, today: NaiveDate) -> Option<u32> {
+    if start == MonthDate::Present {
+        return None;
+    }
+
+    let (start_year, start_month) = start.first_month(today);
+    let (end_year, end_month) = end.last_month(today);
+    let months = (end_year - start_year) * 12 + (end_month as i32) - (start_month as i32) + 1;
+    u32::try_from(months).ok().filter(|months| *months > 0)
+}
+
+pub fn posting_date(text: &str, crawled_at: DateTime<Utc>) -> Option<NaiveDate> {
+    let text = text.trim().to_lowercase();
+    let text = text.strip_prefix("reposted ").unwrap_or(&text);
+    let today = crawled_at.date_naive();
+
+    match text {
+        "just now" | "today" => {
+            return Some(today);
+        }
+        "yesterday" => {
+            return today.pred_opt();
+        }
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(date);
+    }
+
+    let [count, unit, "ago"] = text.split_whitespace().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let count: u32 = count.trim_end_matches('+').parse().ok()?;
+    let elapsed = match unit.trim_end_matches('s') {
+        "second" => Duration::seconds(count.into()),
+        "minute" => Duration::minutes(count.into()),
+        "hour" => Duration::hours(count.into()),
+        "day" => Duration::days(count.into()),
+        "week" => Duration::weeks(count.into()),
+        "month" => {
+            return today.checked_sub_months(Months::new(count));
+        }
+        "year" => {
+            return today.checked_sub_months(Months::new(count.checked_mul(12)?));
+        }
+        _ => {
+            return None;
+        }
+    };
+    Some(crawled_at.checked_sub_signed(elapsed)?.date_naive())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn parses_month_year_and_present() {
+        assert_eq!(MonthDate::parse("Jan 2020"), Some(MonthDate::Month(2020, 1)));
+        assert_eq!(MonthDate::parse(" Sept. 2019 "), Some(MonthDate::Month(2019, 9)));
+        assert_eq!(MonthDate::parse("December 2021"), Some(MonthDate::Month(2021, 12)));
+        assert_eq!(MonthDate::parse("2018"), Some(MonthDate::Year(2018)));
+        assert_eq!(MonthDate::parse("Present"), Some(MonthDate::Present));
+        assert_eq!(MonthDate::parse("Ja 2020"), None);
+        assert_eq!(MonthDate::parse("Foo 2020"), None);
+        assert_eq!(MonthDate::parse("Jan 20"), None);
+        assert_eq!(MonthDate::parse("Jan 2020 - Mar 2021"), None);
+        assert_eq!(MonthDate::Month(2020, 3).iso().as_deref(), Some("2020-03"));
+        assert_eq!(MonthDate::Present.iso(), None);
+    }
+
+    #[test]
+    fn counts_months_inclusively() {
+        let today = date("2024-03-15");
+        let between = |start, end| months_between(start, end, today);
+
+        assert_eq!(between(MonthDate::Month(2020, 1), MonthDate::Month(2022, 3)), Some(27));
+        assert_eq!(between(MonthDate::Month(2020, 1), MonthDate::Month(2020, 1)), Some(1));
+        assert_eq!(between(MonthDate::Year(2018), MonthDate::Year(2019)), Some(24));
+        assert_eq!(between(MonthDate::Month(2023, 11), MonthDate::Present), Some(5));
+        assert_eq!(between(MonthDate::Month(2022, 3), MonthDate::Month(2020, 1)), None);
+        assert_eq!(between(MonthDate::Present, MonthDate::Present), None);
+    }
+
+    #[test]
+    fn resolves_posting_dates_from_the_crawl_time() {
+        let crawled_at = DateTime::parse_from_rfc3339("2024-03-01T00:30:00Z").unwrap().to_utc();
+
+        assert_eq!(posting_date("Just now", crawled_at), Some(date("2024-03-01")));
+        assert_eq!(posting_date("yesterday", crawled_at), Some(date("2024-02-29")));
+        assert_eq!(posting_date("2 hours ago", crawled_at), Some(date("2024-02-29")));
+        assert_eq!(posting_date("Reposted 3 days ago", crawled_at), Some(date("2024-02-27")));
+        assert_eq!(posting_date("1 week ago", crawled_at), Some(date("2024-02-23")));
+        assert_eq!(posting_date("30+ months ago", crawled_at), Some(date("2021-09-01")));
+        assert_eq!(posting_date("1 year ago", crawled_at), Some(date("2023-03-01")));
+        assert_eq!(posting_date("2023-12-24", crawled_at), Some(date("2023-12-24")));
+        assert_eq!(posting_date("a while ago", crawled_at), None);
+    }
+}
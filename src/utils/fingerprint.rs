@@ -0,0 +1,169 @@
+use std::fs;
+use std::sync::Mutex;
+use crate::config::Config;
+use std::collections::HashMap;
+use reqwest::header::HeaderName;
+use serde::{ Deserialize, Serialize };
+use anyhow::{ bail, Context, Result };
+
+pub const HEADER_PROFILE_META: &str = "header_profile";
+pub const SESSION_META: &str = "session";
+
+const BUILTIN_PROFILES: &str = include_str!("header_profiles.json");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderRotation {
+    PerRequest,
+    Sticky,
+}
+
+impl HeaderRotation {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "per_request" => Ok(Self::PerRequest),
+            "sticky" => Ok(Self::Sticky),
+            _ => bail!("Unknown header profile rotation: {value}, expected per_request or sticky"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderProfile {
+    pub name: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl HeaderProfile {
+    pub fn user_agent(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("user-agent"))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn validate(&self, source: &str) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Header profile in {source} has an empty name");
+        }
+
+        for (name, _) in &self.headers {
+            HeaderName::from_bytes(name.as_bytes()).with_context(||
+                format!("Invalid header name {name:?} in profile {} ({source})", self.name)
+            )?;
+        }
+
+        if self.user_agent().is_none() {
+            bail!("Header profile {} in {source} has no User-Agent header", self.name);
+        }
+        Ok(())
+    }
+}
+
+pub struct HeaderProfilePool {
+    profiles: Vec<HeaderProfile>,
+    rotation: HeaderRotation,
+    sticky: Mutex<HashMap<String, usize>>,
+}
+
+impl HeaderProfilePool {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let profiles = match &config.header_profiles_file {
+            Some(path) => {
+                let content = fs
+                    ::read_to_string(path)
+                    .with_context(|| format!("Failed to read header profiles file {path}"))?;
+                Self::parse(&content, path)?
+            }
+            None => Self::parse(BUILTIN_PROFILES, "built-in profiles")?,
+        };
+
+        Ok(Self {
+            profiles,
+            rotation: HeaderRotation::parse(&config.header_profile_rotation)?,
+            sticky: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn parse(content: &str, source: &str) -> Result<Vec<HeaderProfile>> {
+        let profiles: Vec<HeaderProfile> = serde_json
+            ::from_str(content)
+            .with_context(|| format!("Failed to parse header profiles from {source}"))?;
+
+        if profiles.is_empty() {
+            bail!("No header profiles found in {source}");
+        }
+
+        for profile in &profiles {
+            profile.validate(source)?;
+        }
+        Ok(profiles)
+    }
+
+    pub fn profiles(&self) -> &[HeaderProfile] {
+        &self.profiles
+    }
+
+    pub fn rotation(&self) -> HeaderRotation {
+        self.rotation
+    }
+
+    pub fn get(&self, name: &str) -> Option<&HeaderProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    pub fn select(&self, sticky_key: &str) -> &HeaderProfile {
+        let index = match self.rotation {
+            HeaderRotation::PerRequest => fastrand::usize(..self.profiles.len()),
+            HeaderRotation::Sticky =>
+                *self.sticky
+                    .lock()
+                    .unwrap()
+                    .entry(sticky_key.to_string())
+                    .or_insert_with(|| fastrand::usize(..self.profiles.len())),
+        };
+        &self.profiles[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn pool(rotation: &str) -> HeaderProfilePool {
+        let config = Config { header_profile_rotation: rotation.to_string(), ..Config::default() };
+        HeaderProfilePool::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn rotation_only_accepts_header_rotation_names() {
+        assert_eq!(HeaderRotation::parse("sticky").unwrap(), HeaderRotation::Sticky);
+        assert_eq!(HeaderRotation::parse("per_request").unwrap(), HeaderRotation::PerRequest);
+
+        let error = HeaderRotation::parse("session").unwrap_err().to_string();
+        assert!(error.contains("header profile rotation"), "{error}");
+    }
+
+    #[test]
+    fn sticky_keys_keep_their_profile() {
+        let pool = pool("sticky");
+        let first = pool.select("session:a").name.clone();
+        for _ in 0..20 {
+            assert_eq!(pool.select("session:a").name, first);
+        }
+    }
+
+    #[test]
+    fn per_request_rotation_picks_profiles_independently() {
+        let pool = pool("per_request");
+        let names: HashSet<&str> = (0..100).map(|_| pool.select("default").name.as_str()).collect();
+        assert!(names.len() > 1);
+    }
+
+    #[test]
+    fn rejects_profiles_without_user_agent() {
+        let content = r#"[{ "name": "bare", "headers": [["Accept", "*/*"]] }]"#;
+        let error = HeaderProfilePool::parse(content, "profiles.json").unwrap_err().to_string();
+        assert!(error.contains("no User-Agent"), "{error}");
+    }
+}
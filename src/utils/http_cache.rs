@@ -0,0 +1,267 @@
+use std::fs;
+use tracing::warn;
+use std::time::Duration;
+use flate2::Compression;
+use crate::config::Config;
+use chrono::{ DateTime, Utc };
+use std::io::{ Read, Write };
+use std::path::{ Path, PathBuf };
+use anyhow::{ bail, Context, Result };
+use serde::{ Deserialize, Serialize };
+use flate2::{ read::GzDecoder, write::GzEncoder };
+use crate::spiders::{ Request, Response };
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub fingerprint: String,
+    pub request_url: String,
+    pub cached_at: DateTime<Utc>,
+    pub response: Response,
+}
+
+pub struct HttpCache {
+    dir: PathBuf,
+    expiration: Option<Duration>,
+    gzip: bool,
+    offline: bool,
+    meta_keys: Vec<String>,
+}
+
+impl HttpCache {
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let Some(dir) = &config.http_cache_dir else {
+            if config.offline {
+                bail!("Offline mode requires an HTTP cache directory (--cache-dir)");
+            }
+            return Ok(None);
+        };
+
+        Ok(
+            Some(Self {
+                dir: PathBuf::from(dir),
+                expiration: (config.http_cache_expiration_secs > 0).then(||
+                    Duration::from_secs(config.http_cache_expiration_secs)
+                ),
+                gzip: config.http_cache_gzip,
+                offline: config.offline,
+                meta_keys: config.fingerprint_meta_keys.clone(),
+            })
+        )
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    fn entry_path(&self, spider_name: &str, fingerprint: &str, gzip: bool) -> PathBuf {
+        let file_name = if gzip { format!("{fingerprint}.json.gz") } else { format!("{fingerprint}.json") };
+        self.dir.join(spider_name).join(&fingerprint[..2]).join(file_name)
+    }
+
+    pub fn retrieve(&self, spider_name: &str, request: &Request) -> Option<Response> {
+        let fingerprint = request.fingerprint(&self.meta_keys);
+
+        let entry = [self.gzip, !self.gzip].into_iter().find_map(|gzip| {
+            let path = self.entry_path(spider_name, &fingerprint, gzip);
+            if !path.exists() {
+                return None;
+            }
+
+            match Self::read_entry(&path, gzip) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Ignoring unreadable cache entry {}: {:#}", path.display(), e);
+                    None
+                }
+            }
+        })?;
+
+        if !self.offline {
+            if let Some(expiration) = self.expiration {
+                let age = (Utc::now() - entry.cached_at).to_std().unwrap_or(Duration::ZERO);
+                if age > expiration {
+                    return None;
+                }
+            }
+        }
+
+        Some(entry.response)
+    }
+
+    pub fn read_file(path: &Path) -> Option<CachedResponse> {
+        let name = path.file_name()?.to_str()?;
+        if name.ends_with(".json.gz") {
+            Self::read_entry(path, true).ok()
+        } else if name.ends_with(".json") {
+            Self::read_entry(path, false).ok()
+        } else {
+            None
+        }
+    }
+
+    fn read_entry(path: &Path, gzip: bool) -> Result<CachedResponse> {
+        let bytes = fs::read(path)?;
+        let content = if gzip {
+            let mut content = Vec::new();
+            GzDecoder::new(bytes.as_slice()).read_to_end(&mut content)?;
+            content
+        } else {
+            bytes
+        };
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    pub fn store(&self, spider_name: &str, request: &Request, response: &Response) -> Result<()> {
+        let fingerprint = request.fingerprint(&self.meta_keys);
+        let path = self.entry_path(spider_name, &fingerprint, self.gzip);
+        if let Some(parent) = path.parent() {
+            fs
+                ::create_dir_all(parent)
+                .with_context(|| format!("Failed to create cache directory {}", parent.display()))?;
+        }
+
+        let entry = CachedResponse {
+            fingerprint,
+            request_url: request.url.clone(),
+            cached_at: Utc::now(),
+            response: Response { proxy: None, ..response.clone() },
+        };
+        let json = serde_json::to_vec(&entry)?;
+        let content = if self.gzip {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&json)?;
+            encoder.finish()?
+        } else {
+            json
+        };
+
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, content)?;
+        fs
+            ::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to write cache entry {}", path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::collections::HashMap;
+    use crate::engine::Stats;
+    use crate::error::ScrapeError;
+    use crate::utils::HttpClient;
+    use crate::middleware::Downloader;
+    use crate::utils::test_server::TestServer;
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir_name = format!("in-scraper-cache-{name}-{}", std::process::id());
+        let dir = std::env::temp_dir().join(dir_name);
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn config(dir: &Path) -> Config {
+        Config {
+            http_cache_dir: Some(dir.display().to_string()),
+            downloader_middlewares: vec!["http_cache".to_string()],
+            ..Config::default()
+        }
+    }
+
+    fn response(url: &str, body: &str) -> Response {
+        Response {
+            url: url.to_string(),
+            status: 200,
+            headers: HashMap::from([("content-type".to_string(), "text/html".to_string())]),
+            body: body.to_string(),
+            proxy: Some("http://proxy:8080".to_string()),
+            archive_id: None,
+        }
+    }
+
+    fn downloader(config: Config) -> Downloader {
+        let config = Arc::new(config);
+        let http_client = HttpClient::new(config.clone()).unwrap();
+        Downloader::from_config(config, http_client, Arc::new(Stats::new())).unwrap()
+    }
+
+    #[test]
+    fn gzip_entries_round_trip() {
+        let dir = cache_dir("gzip");
+        let cache = HttpCache::from_config(&Config { http_cache_gzip: true, ..config(&dir) })
+            .unwrap()
+            .unwrap();
+        let request = Request::new("https://www.linkedin.com/in/someone".to_string());
+        cache.store("people", &request, &response(&request.url, "<html>cached</html>")).unwrap();
+
+        let fingerprint = request.fingerprint(&[]);
+        let path = cache.entry_path("people", &fingerprint, true);
+        let stored = HttpCache::read_file(&path);
+        let retrieved = cache.retrieve("people", &request);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let stored = stored.unwrap();
+        assert_eq!(stored.request_url, request.url);
+        assert_eq!(stored.response.proxy, None);
+        let retrieved = retrieved.unwrap();
+        assert_eq!(retrieved.body, "<html>cached</html>");
+        assert_eq!(retrieved.header("content-type"), Some("text/html"));
+    }
+
+    #[test]
+    fn expired_entries_are_misses_unless_offline() {
+        let dir = cache_dir("expired");
+        let config = Config { http_cache_expiration_secs: 60, ..config(&dir) };
+        let cache = HttpCache::from_config(&config).unwrap().unwrap();
+        let request = Request::new("https://www.linkedin.com/in/someone".to_string());
+        cache.store("people", &request, &response(&request.url, "old")).unwrap();
+
+        let path = cache.entry_path("people", &request.fingerprint(&[]), false);
+        let mut entry = HttpCache::read_file(&path).unwrap();
+        entry.cached_at -= chrono::Duration::minutes(5);
+        fs::write(&path, serde_json::to_vec(&entry).unwrap()).unwrap();
+
+        let online = cache.retrieve("people", &request);
+        let offline = HttpCache::from_config(&Config { offline: true, ..config })
+            .unwrap()
+            .unwrap()
+            .retrieve("people", &request);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(online.is_none());
+        assert_eq!(offline.unwrap().body, "old");
+    }
+
+    #[tokio::test]
+    async fn offline_misses_fail_without_a_request() {
+        let server = TestServer::start(|_| (200, "live".to_string())).await;
+        let dir = cache_dir("offline");
+        let downloader = downloader(Config { offline: true, ..config(&dir) });
+
+        let request = Request::new(format!("{}/in/someone", server.url));
+        let error = downloader.fetch(&request, "people").await.unwrap_err();
+        let _ = fs::remove_dir_all(&dir);
+        assert!(matches!(error.downcast_ref(), Some(ScrapeError::CacheMiss { .. })));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn corrupt_entries_are_refetched_and_replaced() {
+        let server = TestServer::start(|_| (200, "live".to_string())).await;
+        let dir = cache_dir("corrupt");
+        let config = config(&dir);
+        let cache = HttpCache::from_config(&config).unwrap().unwrap();
+        let request = Request::new(format!("{}/in/someone", server.url));
+        let path = cache.entry_path("people", &request.fingerprint(&[]), false);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{ not json").unwrap();
+
+        let downloader = downloader(config);
+        let fetched = downloader.fetch(&request, "people").await.unwrap();
+        let replayed = downloader.fetch(&request, "people").await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((fetched.body.as_str(), replayed.body.as_str()), ("live", "live"));
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -1,63 +1,118 @@
-// use std::env;
-// use dotenv::dotenv;
 use std::sync::Arc;
 use tokio::time::sleep;
 use std::time::Duration;
 use crate::config::Config;
-use tracing::{ error, warn };
+use std::collections::HashMap;
+use crate::error::ScrapeError;
 use anyhow::{ Result, Context };
-use reqwest::{ Client, Response, StatusCode };
+use tracing::{ debug, error, warn };
+use reqwest::header::USER_AGENT;
+use crate::utils::fingerprint::SESSION_META;
+use crate::utils::proxy::{ Proxy, ProxyPool, PROXY_META };
+use reqwest::{ cookie::Jar, Client, Response, StatusCode };
+use crate::spiders::{ Request, Response as SpiderResponse };
+use crate::utils::retry::{ parse_retry_after, retry_after, ExponentialBackoff };
+use crate::utils::retry::{ RetryDecision, RetryPolicy };
 
+const BLOCKED_STATUS: u16 = 999;
+
+#[derive(Clone)]
 pub struct HttpClient {
     client: Client,
-    config: Arc<Config>,
-    // li_at_cookie: String,
-    // jsession_id_cookie: String,
+    user_agent: String,
+    cookie_jar: Arc<Jar>,
+    retry_policy: Arc<dyn RetryPolicy>,
+    proxy_pool: Option<Arc<ProxyPool>>,
 }
 
 impl HttpClient {
     pub fn new(config: Arc<Config>) -> Result<Self> {
-        // dotenv().ok();
-        // let li_at_cookie = env
-        //     ::var("LINKEDIN_COOKIE_LI_AT")
-        //     .context("LINKEDIN_COOKIE_LI_AT variable not set in .env file")?;
+        let cookie_jar = Arc::new(Jar::default());
+        let client = Self::build_client(&config, &cookie_jar, None)?;
+        let proxy_pool = ProxyPool::from_config(&config, |proxy| {
+            Self::build_client(&config, &cookie_jar, Some(proxy))
+        })?.map(Arc::new);
 
-        // let jsession_id_cookie = env
-        //     ::var("LINKEDIN_COOKIE_JSESSIONID")
-        //     .context("LINKEDIN_COOKIE_JSESSIONID variable not set in .env file")?;
+        let retry_policy = Arc::new(ExponentialBackoff::from_config(&config));
+        Ok(Self {
+            client,
+            user_agent: config.user_agent.clone(),
+            cookie_jar,
+            retry_policy,
+            proxy_pool,
+        })
+    }
 
-        let client = Client::builder()
+    fn build_client(
+        config: &Config,
+        cookie_jar: &Arc<Jar>,
+        proxy: Option<reqwest::Proxy>
+    ) -> Result<Client> {
+        let mut builder = Client::builder()
+            .cookie_provider(cookie_jar.clone())
             .timeout(Duration::from_secs(config.request_timeout))
-            .user_agent(&config.user_agent)
             .gzip(true)
             .pool_max_idle_per_host(10)
-            .tcp_keepalive(Duration::from_secs(60))
-            .build()
-            .context("Failed to build HTTP client")?;
+            .tcp_keepalive(Duration::from_secs(60));
 
-        Ok(Self {
-            client,
-            config,
-            // li_at_cookie,
-            // jsession_id_cookie
-        })
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy);
+        }
+
+        builder.build().context("Failed to build HTTP client")
+    }
+
+    pub fn proxy_pool(&self) -> Option<&Arc<ProxyPool>> {
+        self.proxy_pool.as_ref()
+    }
+
+    fn select_client(&self, request: Option<&Request>) -> (Client, Option<Arc<Proxy>>) {
+        match &self.proxy_pool {
+            Some(pool) => {
+                let meta = |key| request?.meta.get(key).map(String::as_str);
+                let proxy = meta(PROXY_META)
+                    .and_then(|label| pool.get(label))
+                    .unwrap_or_else(|| pool.select(meta(SESSION_META)));
+                (proxy.client().clone(), Some(proxy))
+            }
+            None => (self.client.clone(), None),
+        }
+    }
+
+    fn record_proxy_outcome(&self, proxy: &Proxy, result: &Result<Response, reqwest::Error>) {
+        let Some(pool) = &self.proxy_pool else {
+            return;
+        };
+
+        match result {
+            Ok(response) =>
+                match response.status().as_u16() {
+                    403 | 407 | 429 | BLOCKED_STATUS => pool.record_failure(proxy, true),
+                    502..=504 => pool.record_failure(proxy, false),
+                    _ => pool.record_success(proxy),
+                }
+            Err(_) => pool.record_failure(proxy, false),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: Arc<dyn RetryPolicy>) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn cookie_jar(&self) -> &Arc<Jar> {
+        &self.cookie_jar
+    }
+
+    pub fn retry_policy(&self) -> &Arc<dyn RetryPolicy> {
+        &self.retry_policy
     }
 
     pub async fn get(&self, url: &str) -> Result<Response> {
-        // let cookie_header = format!(
-        //     "li_at={}; jsessionid={}",
-        //     self.li_at_cookie,
-        //     self.jsession_id_cookie
-        // );
-        self.execute_with_retry(||
-            self.client
-                .get(url)
-                // .header("cookie", &cookie_header)
-                // .header("referer", "https://www.linkedin.com/feed/")
-                // .header("accept", "application/vnd.linkedin.normalized+json+2.1")
-                // .header("csrf-token", self.jsession_id_cookie.replace("\"", ""))
-                .send()
-        ).await
+        let (response, _) = self.execute_with_retry(url, None, false, |client| {
+            client.get(url).header(USER_AGENT, &self.user_agent).send()
+        }).await?;
+        Ok(response)
     }
 
     pub async fn get_text(&self, url: &str) -> Result<String> {
@@ -66,68 +121,196 @@ impl HttpClient {
         Ok(text)
     }
 
-    async fn execute_with_retry<F, Fut>(&self, request_fn: F) -> Result<Response>
-        where F: Fn() -> Fut, Fut: std::future::Future<Output = Result<Response, reqwest::Error>>
+    pub async fn execute(&self, request: &Request) -> Result<SpiderResponse> {
+        Self::check_status(self.download(request).await?)
+    }
+
+    pub async fn download(&self, request: &Request) -> Result<SpiderResponse> {
+        let send = |client: &Client| {
+            let mut builder = client.get(&request.url);
+            for (name, value) in &request.headers {
+                builder = builder.header(name, value);
+            }
+            if request.header("user-agent").is_none() {
+                builder = builder.header(USER_AGENT, &self.user_agent);
+            }
+            builder.send()
+        };
+        let (response, proxy) = self.execute_with_retry(&request.url, Some(request), true, send)
+            .await?;
+
+        let url = response.url().to_string();
+        let status = response.status().as_u16();
+        let mut headers: HashMap<String, String> = HashMap::new();
+        for (name, value) in response.headers() {
+            let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+            headers
+                .entry(name.as_str().to_string())
+                .and_modify(|existing| {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                })
+                .or_insert(value);
+        }
+        let body = response.text().await.context("Failed to get response text")?;
+
+        Ok(SpiderResponse {
+            url,
+            status,
+            headers,
+            body,
+            proxy: proxy.map(|proxy| proxy.label().to_string()),
+            archive_id: None,
+        })
+    }
+
+    pub fn check_status(response: SpiderResponse) -> Result<SpiderResponse> {
+        if (200..400).contains(&response.status) {
+            return Ok(response);
+        }
+        let retry_after = response.header("retry-after").and_then(parse_retry_after);
+        Err(status_error(response.url, response.status, retry_after).into())
+    }
+
+    pub async fn fetch(&self, url: &str) -> Result<Response> {
+        let (client, proxy) = self.select_client(None);
+        let result = client.get(url).header(USER_AGENT, &self.user_agent).send().await;
+        if let Some(proxy) = &proxy {
+            self.record_proxy_outcome(proxy, &result);
+        }
+        result.context("Failed to send request")
+    }
+
+    async fn execute_with_retry<F, Fut>(
+        &self,
+        url: &str,
+        request: Option<&Request>,
+        defer_status_retries: bool,
+        request_fn: F
+    ) -> Result<(Response, Option<Arc<Proxy>>)>
+        where
+            F: Fn(&Client) -> Fut,
+            Fut: std::future::Future<Output = Result<Response, reqwest::Error>>
     {
-        let mut retries = 0;
-        let max_retries = self.config.max_retries;
-        let retry_delay = Duration::from_millis(self.config.retry_delay_ms);
+        let mut attempt = 0;
 
         loop {
-            match request_fn().await {
+            let (client, proxy) = self.select_client(request);
+            let result = request_fn(&client).await;
+
+            if let Some(proxy) = &proxy {
+                debug!("Fetched {} via proxy {}", url, proxy.label());
+                self.record_proxy_outcome(proxy, &result);
+            }
+
+            let decision = match result {
                 Ok(response) => {
                     let status = response.status();
 
-                    if status.is_success() {
-                        return Ok(response);
-                    }
-
-                    if status == StatusCode::TOO_MANY_REQUESTS {
-                        warn!("Rate limited (429), retrying after delay");
-                        if retries < max_retries {
-                            retries += 1;
-                            sleep(retry_delay * retries).await;
-                            continue;
-                        }
+                    if status.is_success() || status.is_redirection() || defer_status_retries {
+                        return Ok((response, proxy));
                     }
 
-                    if status.is_server_error() {
-                        error!("Server error: {}, retrying", status);
-                        if retries < max_retries {
-                            retries += 1;
-                            sleep(retry_delay * retries).await;
-                            continue;
-                        }
+                    let retry_after = retry_after(response.headers());
+                    let decision = self.retry_policy.on_status(attempt, status, retry_after);
+                    if decision == RetryDecision::GiveUp {
+                        error!("HTTP error {} for {}", status, url);
+                        let error = status_error(url.to_string(), status.as_u16(), retry_after);
+                        return Err(error.into());
                     }
 
-                    if status.is_client_error() {
-                        error!("Client error: {}", status);
-                        return Err(anyhow::anyhow!("HTTP client error: {}", status));
+                    if status == StatusCode::TOO_MANY_REQUESTS {
+                        warn!("Rate limited (429) on {}", url);
+                    } else {
+                        warn!("HTTP error {} on {}", status, url);
                     }
-
-                    return Ok(response);
+                    decision
                 }
                 Err(e) => {
                     error!("Request failed: {}", e);
 
-                    if retries < max_retries {
-                        retries += 1;
-                        warn!("Retrying request (attempt {}/{})", retries, max_retries + 1);
-                        sleep(retry_delay * retries).await;
-                        continue;
+                    let decision = self.retry_policy.on_error(attempt, &e);
+                    if decision == RetryDecision::GiveUp {
+                        return Err(
+                            anyhow::anyhow!("Request failed after {} retries: {}", attempt, e)
+                        );
                     }
-
-                    return Err(
-                        anyhow::anyhow!("Request failed after {} retries: {}", max_retries, e)
-                    );
+                    decision
                 }
+            };
+
+            if let RetryDecision::Retry(delay) = decision {
+                attempt += 1;
+                warn!(
+                    "Retrying {} in {:?} (attempt {}/{})",
+                    url,
+                    delay,
+                    attempt,
+                    self.retry_policy.max_retries()
+                );
+                sleep(delay).await;
             }
         }
     }
 }
 
-impl Clone for HttpClient {
-    fn clone(&self) -> Self {
-        Self::new(self.config.clone()).expect("Failed to clone HttpClient")
+fn status_error(url: String, status: u16, retry_after: Option<Duration>) -> ScrapeError {
+    if status == BLOCKED_STATUS {
+        ScrapeError::Blocked { url, status }
+    } else {
+        ScrapeError::HttpStatus { url, status, retry_after }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio::net::TcpListener;
+    use tokio::io::{ AsyncReadExt, AsyncWriteExt };
+
+    #[tokio::test]
+    async fn clones_share_the_connection_pool_and_cookie_jar() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests: Arc<Mutex<Vec<(usize, String)>>> = Arc::default();
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            let mut connection = 0;
+            while let Ok((mut stream, _)) = listener.accept().await {
+                connection += 1;
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let mut buffer = [0; 1024];
+                    let mut head = Vec::new();
+                    while let Ok(read @ 1..) = stream.read(&mut buffer).await {
+                        head.extend_from_slice(&buffer[..read]);
+                        if !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                            continue;
+                        }
+                        let request = String::from_utf8_lossy(&head).to_lowercase();
+                        recorded.lock().unwrap().push((connection, request));
+                        head.clear();
+
+                        let response = "HTTP/1.1 200 OK\r\nSet-Cookie: session=1\r\n\
+                            Content-Length: 2\r\n\r\nok";
+                        let _ = stream.write_all(response.as_bytes()).await;
+                    }
+                });
+            }
+        });
+
+        let client = HttpClient::new(Arc::new(Config::default())).unwrap();
+        let clone = client.clone();
+        assert_eq!(client.get_text(&url).await.unwrap(), "ok");
+        assert_eq!(clone.get_text(&url).await.unwrap(), "ok");
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, requests[1].0, "the clone opened a new connection");
+        assert!(!requests[0].1.contains("cookie: session=1"));
+        assert!(requests[1].1.contains("cookie: session=1"));
+        assert!(Arc::ptr_eq(client.cookie_jar(), clone.cookie_jar()));
     }
 }
@@ -1,5 +1,22 @@
+pub mod proxy;
+pub mod retry;
+pub mod robots;
+pub mod classifier;
+pub mod http_cache;
+pub mod session;
+pub mod fingerprint;
 pub mod http_client;
 pub mod selector_utils;
+pub mod date_utils;
+
+#[cfg(test)]
+pub mod test_server;
 
 pub use http_client::HttpClient;
-pub use selector_utils::parse_selector;
+pub use session::Session;
+pub use proxy::{ ProxyPool, ProxyRotation, PROXY_META };
+pub use fingerprint::{ HeaderProfile, HeaderProfilePool, HeaderRotation };
+pub use retry::{ ExponentialBackoff, RetryDecision, RetryPolicy };
+pub use classifier::ResponseClassifier;
+pub use http_cache::{ CachedResponse, HttpCache };
+pub use robots::{ RobotsCache, RobotsTxt };
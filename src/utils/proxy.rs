@@ -0,0 +1,321 @@
+use std::fs;
+use url::Url;
+use tracing::warn;
+use reqwest::Client;
+use std::sync::{ Arc, Mutex };
+use crate::config::Config;
+use std::collections::HashMap;
+use anyhow::{ bail, Context, Result };
+use std::time::{ Duration, Instant };
+use std::sync::atomic::{ AtomicUsize, Ordering };
+
+pub const PROXY_META: &str = "proxy";
+
+const DEFAULT_SESSION: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyRotation {
+    PerRequest,
+    Sticky,
+}
+
+impl ProxyRotation {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "per_request" | "per-request" => Ok(Self::PerRequest),
+            "sticky" | "session" => Ok(Self::Sticky),
+            _ => bail!("Unknown rotation: {value}, expected per_request or sticky"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProxyHealth {
+    consecutive_failures: u32,
+    banned_until: Option<Instant>,
+    requests: u64,
+    failures: u64,
+    bans: u64,
+}
+
+pub struct Proxy {
+    label: String,
+    client: Client,
+    health: Mutex<ProxyHealth>,
+}
+
+impl Proxy {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    fn is_available(&self, now: Instant) -> bool {
+        self.health
+            .lock()
+            .unwrap()
+            .banned_until.is_none_or(|until| until <= now)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProxyReport {
+    pub label: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub bans: u64,
+}
+
+pub struct ProxyPool {
+    proxies: Vec<Arc<Proxy>>,
+    rotation: ProxyRotation,
+    next: AtomicUsize,
+    sticky: Mutex<HashMap<String, usize>>,
+    max_failures: u32,
+    cooldown: Duration,
+}
+
+impl ProxyPool {
+    pub fn from_config(
+        config: &Config,
+        build_client: impl Fn(reqwest::Proxy) -> Result<Client>
+    ) -> Result<Option<Self>> {
+        let mut urls = config.proxies.clone();
+
+        if let Some(path) = &config.proxy_file {
+            let content = fs
+                ::read_to_string(path)
+                .with_context(|| format!("Failed to read proxy file {path}"))?;
+            urls.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string)
+            );
+        }
+
+        if urls.is_empty() {
+            return Ok(None);
+        }
+
+        let mut proxies = Vec::new();
+        for url in urls {
+            let parsed = Url::parse(&url).with_context(|| format!("Invalid proxy URL: {url}"))?;
+            if !matches!(parsed.scheme(), "http" | "https" | "socks5" | "socks5h") {
+                bail!("Unsupported proxy scheme in {url}, expected http, https or socks5");
+            }
+
+            let proxy = reqwest::Proxy
+                ::all(parsed.as_str())
+                .with_context(|| format!("Invalid proxy URL: {url}"))?;
+            proxies.push(
+                Arc::new(Proxy {
+                    label: Self::label(&parsed),
+                    client: build_client(proxy)?,
+                    health: Mutex::new(ProxyHealth::default()),
+                })
+            );
+        }
+
+        Ok(
+            Some(Self {
+                proxies,
+                rotation: ProxyRotation::parse(&config.proxy_rotation)?,
+                next: AtomicUsize::new(0),
+                sticky: Mutex::new(HashMap::new()),
+                max_failures: config.proxy_max_failures.max(1),
+                cooldown: Duration::from_millis(config.proxy_cooldown_ms),
+            })
+        )
+    }
+
+    fn label(url: &Url) -> String {
+        match url.port() {
+            Some(port) => format!("{}://{}:{}", url.scheme(), url.host_str().unwrap_or(""), port),
+            None => format!("{}://{}", url.scheme(), url.host_str().unwrap_or("")),
+        }
+    }
+
+    pub fn rotation(&self) -> ProxyRotation {
+        self.rotation
+    }
+
+    pub fn get(&self, label: &str) -> Option<Arc<Proxy>> {
+        let now = Instant::now();
+        self.proxies
+            .iter()
+            .find(|proxy| proxy.label == label && proxy.is_available(now))
+            .cloned()
+    }
+
+    pub fn select(&self, session: Option<&str>) -> Arc<Proxy> {
+        let now = Instant::now();
+        let count = self.proxies.len();
+        let session = session.unwrap_or(DEFAULT_SESSION);
+        let mut sticky = self.sticky.lock().unwrap();
+        let start = match (self.rotation, sticky.get(session)) {
+            (ProxyRotation::Sticky, Some(index)) => *index,
+            _ => self.next.fetch_add(1, Ordering::Relaxed),
+        };
+
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            if self.proxies[index].is_available(now) {
+                if self.rotation == ProxyRotation::Sticky {
+                    sticky.insert(session.to_string(), index);
+                }
+                return self.proxies[index].clone();
+            }
+        }
+
+        let proxy = self.proxies
+            .iter()
+            .min_by_key(|proxy| proxy.health.lock().unwrap().banned_until)
+            .expect("Proxy pool is never empty")
+            .clone();
+        warn!("All proxies are cooling down, falling back to {}", proxy.label);
+        proxy
+    }
+
+    pub fn record_success(&self, proxy: &Proxy) {
+        let mut health = proxy.health.lock().unwrap();
+        health.requests += 1;
+        health.consecutive_failures = 0;
+    }
+
+    pub fn record_failure(&self, proxy: &Proxy, banned: bool) {
+        let mut health = proxy.health.lock().unwrap();
+        health.requests += 1;
+        health.failures += 1;
+        health.consecutive_failures += 1;
+
+        if banned || health.consecutive_failures >= self.max_failures {
+            health.bans += 1;
+            health.consecutive_failures = 0;
+            health.banned_until = Some(Instant::now() + self.cooldown);
+            warn!("Proxy {} marked bad, cooling down for {:?}", proxy.label, self.cooldown);
+        }
+    }
+
+    pub fn report(&self) -> Vec<ProxyReport> {
+        self.proxies
+            .iter()
+            .map(|proxy| {
+                let health = proxy.health.lock().unwrap();
+                ProxyReport {
+                    label: proxy.label.clone(),
+                    requests: health.requests,
+                    failures: health.failures,
+                    bans: health.bans,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spiders::Request;
+    use crate::utils::HttpClient;
+    use crate::utils::fingerprint::SESSION_META;
+    use crate::utils::test_server::TestServer;
+
+    const TARGET: &str = "http://target.invalid/page";
+
+    async fn proxies(statuses: &[u16]) -> Vec<TestServer> {
+        let mut servers = Vec::new();
+        for status in statuses.iter().copied() {
+            servers.push(TestServer::start(move |_| (status, "proxied".to_string())).await);
+        }
+        servers
+    }
+
+    fn client(servers: &[TestServer], rotation: &str) -> HttpClient {
+        let config = Config {
+            proxies: servers.iter().map(|server| server.url.clone()).collect(),
+            proxy_rotation: rotation.to_string(),
+            proxy_max_failures: 2,
+            max_retries: 0,
+            ..Config::default()
+        };
+        HttpClient::new(Arc::new(config)).unwrap()
+    }
+
+    fn request(session: Option<&str>) -> Request {
+        let request = Request::new(TARGET.to_string());
+        match session {
+            Some(session) => request.with_meta(SESSION_META.to_string(), session.to_string()),
+            None => request,
+        }
+    }
+
+    fn served(servers: &[TestServer]) -> Vec<usize> {
+        servers.iter().map(|server| server.requests().len()).collect()
+    }
+
+    #[tokio::test]
+    async fn per_request_rotation_is_round_robin_through_the_proxies() {
+        let servers = proxies(&[200, 200, 200]).await;
+        let client = client(&servers, "per_request");
+
+        let mut labels = Vec::new();
+        for _ in 0..4 {
+            labels.push(client.execute(&request(None)).await.unwrap().proxy.unwrap());
+        }
+
+        let urls: Vec<&str> = servers.iter().map(|server| server.url.as_str()).collect();
+        assert_eq!(labels, [urls[0], urls[1], urls[2], urls[0]]);
+        assert_eq!(servers[0].requests()[0], format!("GET {TARGET} HTTP/1.1"));
+    }
+
+    #[tokio::test]
+    async fn banned_and_failing_proxies_are_cooled_down() {
+        let servers = proxies(&[200, 403, 502]).await;
+        let client = client(&servers, "per_request");
+
+        for _ in 0..9 {
+            let _ = client.execute(&request(None)).await;
+        }
+
+        let report = client.proxy_pool().unwrap().report();
+        assert_eq!(served(&servers), [6, 1, 2]);
+        assert_eq!((report[1].failures, report[1].bans), (1, 1));
+        assert_eq!((report[2].failures, report[2].bans), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn sticky_rotation_keeps_one_proxy_per_session() {
+        let servers = proxies(&[200, 200, 200]).await;
+        let client = client(&servers, "sticky");
+
+        let mut first = Vec::new();
+        for session in [Some("a"), Some("b"), None] {
+            first.push(client.execute(&request(session)).await.unwrap().proxy.unwrap());
+        }
+        for (session, label) in [Some("a"), Some("b"), None].into_iter().zip(&first) {
+            for _ in 0..2 {
+                assert_eq!(&client.execute(&request(session)).await.unwrap().proxy.unwrap(), label);
+            }
+        }
+
+        assert_eq!(served(&servers), [3, 3, 3]);
+    }
+
+    #[tokio::test]
+    async fn sticky_session_moves_on_when_its_proxy_is_banned() {
+        let servers = proxies(&[403, 200]).await;
+        let client = client(&servers, "sticky");
+
+        assert!(client.execute(&request(Some("a"))).await.is_err());
+        for _ in 0..3 {
+            let response = client.execute(&request(Some("a"))).await.unwrap();
+            assert_eq!(response.proxy.unwrap(), servers[1].url);
+        }
+        assert_eq!(served(&servers), [1, 3]);
+    }
+}
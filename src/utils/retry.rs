@@ -0,0 +1,255 @@
+use std::io;
+use std::time::Duration;
+use crate::config::Config;
+use crate::error::ScrapeError;
+use chrono::{ DateTime, Utc };
+use std::error::Error as StdError;
+use reqwest::{ header::{ HeaderMap, RETRY_AFTER }, StatusCode };
+
+const BLOCKED_STATUS: u16 = 999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    Retry(Duration),
+    GiveUp,
+}
+
+pub trait RetryPolicy: Send + Sync {
+    fn max_retries(&self) -> u32;
+
+    fn backoff(&self, attempt: u32) -> Duration;
+
+    fn on_status(
+        &self,
+        attempt: u32,
+        status: StatusCode,
+        retry_after: Option<Duration>
+    ) -> RetryDecision;
+
+    fn on_error(&self, attempt: u32, error: &reqwest::Error) -> RetryDecision;
+
+    fn on_scrape_error(&self, attempt: u32, error: &ScrapeError) -> RetryDecision {
+        match error.status().and_then(|status| StatusCode::from_u16(status).ok()) {
+            Some(status) => self.on_status(attempt, status, error.retry_after()),
+            None if error.is_retryable() && attempt < self.max_retries() =>
+                RetryDecision::Retry(self.backoff(attempt)),
+            None => RetryDecision::GiveUp,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoff {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub blocked_cooloff: Duration,
+}
+
+impl ExponentialBackoff {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            base_delay: Duration::from_millis(config.retry_delay_ms),
+            max_delay: Duration::from_millis(config.retry_max_delay_ms),
+            blocked_cooloff: Duration::from_millis(config.blocked_cooloff_ms),
+        }
+    }
+
+    fn retry_or_give_up(&self, attempt: u32, delay: Duration) -> RetryDecision {
+        if attempt < self.max_retries { RetryDecision::Retry(delay) } else { RetryDecision::GiveUp }
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        ceiling.mul_f64(fastrand::f64())
+    }
+
+    fn on_status(
+        &self,
+        attempt: u32,
+        status: StatusCode,
+        retry_after: Option<Duration>
+    ) -> RetryDecision {
+        match status.as_u16() {
+            BLOCKED_STATUS => {
+                let jitter = self.backoff(attempt);
+                self.retry_or_give_up(attempt, self.blocked_cooloff + jitter)
+            }
+            429 | 503 => {
+                let delay = retry_after
+                    .map(|delay| delay.min(self.max_delay.max(self.blocked_cooloff)))
+                    .unwrap_or_else(|| self.backoff(attempt));
+                self.retry_or_give_up(attempt, delay)
+            }
+            408 | 500 | 502 | 504 => self.retry_or_give_up(attempt, self.backoff(attempt)),
+            _ => RetryDecision::GiveUp,
+        }
+    }
+
+    fn on_error(&self, attempt: u32, error: &reqwest::Error) -> RetryDecision {
+        if is_retryable_error(error) {
+            self.retry_or_give_up(attempt, self.backoff(attempt))
+        } else {
+            RetryDecision::GiveUp
+        }
+    }
+}
+
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    parse_retry_after(headers.get(RETRY_AFTER)?.to_str().ok()?)
+}
+
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((date - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+}
+
+pub fn is_retryable_error(error: &reqwest::Error) -> bool {
+    if error.is_builder() || error.is_redirect() || error.is_decode() {
+        return false;
+    }
+
+    if error.is_timeout() {
+        return true;
+    }
+
+    let mut source = error.source();
+    while let Some(cause) = source {
+        if let Some(io_error) = cause.downcast_ref::<io::Error>() {
+            if
+                matches!(
+                    io_error.kind(),
+                    io::ErrorKind::ConnectionReset |
+                        io::ErrorKind::ConnectionAborted |
+                        io::ErrorKind::ConnectionRefused |
+                        io::ErrorKind::BrokenPipe |
+                        io::ErrorKind::TimedOut |
+                        io::ErrorKind::UnexpectedEof
+                )
+            {
+                return true;
+            }
+        }
+
+        let message = cause.to_string().to_ascii_lowercase();
+        if ["certificate", "tls", "ssl", "handshake"].iter().any(|marker| message.contains(marker)) {
+            return false;
+        }
+
+        source = cause.source();
+    }
+
+    error.is_connect() || error.is_request() || error.is_body()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn policy() -> ExponentialBackoff {
+        ExponentialBackoff {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            blocked_cooloff: Duration::from_secs(300),
+        }
+    }
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds() {
+        assert_eq!(retry_after(&headers(" 120 ")), Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn retry_after_accepts_http_dates() {
+        let date = (Utc::now() + chrono::Duration::seconds(90)).to_rfc2822();
+        let delay = retry_after(&headers(&date)).unwrap();
+        assert!(delay > Duration::from_secs(85) && delay <= Duration::from_secs(90), "{delay:?}");
+
+        let past = (Utc::now() - chrono::Duration::seconds(90)).to_rfc2822();
+        assert_eq!(retry_after(&headers(&past)), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_ignores_missing_and_invalid_values() {
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+        assert_eq!(retry_after(&headers("soon")), None);
+        assert_eq!(retry_after(&headers("-5")), None);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = policy();
+        for attempt in 0..20 {
+            assert!(policy.backoff(attempt) <= policy.max_delay);
+        }
+        assert!(policy.backoff(0) <= policy.base_delay);
+    }
+
+    #[test]
+    fn status_decisions() {
+        let policy = policy();
+        let too_many = StatusCode::TOO_MANY_REQUESTS;
+        let retry_after = Some(Duration::from_secs(30));
+        assert_eq!(
+            policy.on_status(0, too_many, retry_after),
+            RetryDecision::Retry(Duration::from_secs(30))
+        );
+        assert_eq!(policy.on_status(3, too_many, retry_after), RetryDecision::GiveUp);
+        assert_eq!(policy.on_status(0, StatusCode::NOT_FOUND, None), RetryDecision::GiveUp);
+
+        let blocked = StatusCode::from_u16(BLOCKED_STATUS).unwrap();
+        let RetryDecision::Retry(cooloff) = policy.on_status(0, blocked, None) else {
+            panic!("999 must be retried");
+        };
+        assert!(cooloff >= policy.blocked_cooloff);
+    }
+
+    #[test]
+    fn scrape_errors_use_the_status_or_backoff() {
+        let policy = policy();
+        let url = "https://www.linkedin.com/in/someone".to_string();
+
+        let blocked = ScrapeError::Blocked { url: url.clone(), status: BLOCKED_STATUS };
+        let decision = policy.on_scrape_error(0, &blocked);
+        assert!(matches!(decision, RetryDecision::Retry(delay) if delay >= policy.blocked_cooloff));
+
+        let unavailable = ScrapeError::HttpStatus {
+            url: url.clone(),
+            status: 503,
+            retry_after: Some(Duration::from_secs(5)),
+        };
+        assert_eq!(
+            policy.on_scrape_error(1, &unavailable),
+            RetryDecision::Retry(Duration::from_secs(5))
+        );
+
+        let authwall = ScrapeError::AuthWall { url: url.clone() };
+        assert!(matches!(policy.on_scrape_error(2, &authwall), RetryDecision::Retry(_)));
+        assert_eq!(policy.on_scrape_error(3, &authwall), RetryDecision::GiveUp);
+
+        let empty = ScrapeError::EmptyPage { url };
+        assert_eq!(policy.on_scrape_error(0, &empty), RetryDecision::GiveUp);
+    }
+}
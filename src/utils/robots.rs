@@ -0,0 +1,380 @@
+use url::Url;
+use std::sync::Arc;
+use tokio::time::sleep;
+use tokio::sync::{ Mutex, OnceCell };
+use crate::config::Config;
+use tracing::{ debug, warn };
+use std::collections::HashMap;
+use crate::utils::HttpClient;
+use std::time::{ Duration, Instant };
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: String,
+    allow: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RobotsTxt {
+    groups: Vec<Group>,
+    disallow_all: bool,
+}
+
+impl RobotsTxt {
+    pub fn parse(content: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut in_agent_block = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if !in_agent_block {
+                        groups.push(Group::default());
+                        in_agent_block = true;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    in_agent_block = false;
+                    if value.is_empty() {
+                        continue;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.rules.push(Rule {
+                            pattern: value.to_string(),
+                            allow: key == "allow",
+                        });
+                    }
+                }
+                "crawl-delay" => {
+                    in_agent_block = false;
+                    if let Some(group) = groups.last_mut() {
+                        group.crawl_delay = value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                            .map(Duration::from_secs_f64);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self { groups, disallow_all: false }
+    }
+
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    pub fn disallow_all() -> Self {
+        Self { groups: Vec::new(), disallow_all: true }
+    }
+
+    fn matching_groups(&self, user_agent: &str) -> Vec<&Group> {
+        let user_agent = user_agent.to_ascii_lowercase();
+        let specific: Vec<&Group> = self.groups
+            .iter()
+            .filter(|group| {
+                group.agents
+                    .iter()
+                    .any(|agent| {
+                        !agent.is_empty() && agent != "*" && user_agent.contains(agent.as_str())
+                    })
+            })
+            .collect();
+
+        if !specific.is_empty() {
+            return specific;
+        }
+
+        self.groups
+            .iter()
+            .filter(|group| group.agents.iter().any(|agent| agent == "*"))
+            .collect()
+    }
+
+    pub fn is_allowed(&self, user_agent: &str, url: &Url) -> bool {
+        if self.disallow_all {
+            return false;
+        }
+
+        let mut path = url.path().to_string();
+        if let Some(query) = url.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+
+        if path == "/robots.txt" {
+            return true;
+        }
+
+        let mut best: Option<&Rule> = None;
+        for group in self.matching_groups(user_agent) {
+            for rule in group.rules.iter().filter(|rule| pattern_matches(&rule.pattern, &path)) {
+                best = match best {
+                    Some(current) if current.pattern.len() > rule.pattern.len() => Some(current),
+                    Some(current) if current.pattern.len() == rule.pattern.len() && current.allow =>
+                        Some(current),
+                    _ => Some(rule),
+                };
+            }
+        }
+
+        best.is_none_or(|rule| rule.allow)
+    }
+
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.matching_groups(user_agent)
+            .iter()
+            .filter_map(|group| group.crawl_delay)
+            .max()
+    }
+}
+
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(stripped) => (stripped, true),
+        None => (pattern, false),
+    };
+
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let Some(mut rest) = path.strip_prefix(parts[0]) else {
+        return false;
+    };
+
+    if parts.len() == 1 {
+        return !anchored || rest.is_empty();
+    }
+
+    for (index, part) in parts[1..].iter().enumerate() {
+        if anchored && index == parts.len() - 2 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => {
+                rest = &rest[pos + part.len()..];
+            }
+            None => {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+pub struct RobotsCache {
+    user_agent: String,
+    http_client: HttpClient,
+    entries: Mutex<HashMap<String, Arc<OnceCell<Arc<RobotsTxt>>>>>,
+    last_access: Mutex<HashMap<String, Instant>>,
+}
+
+impl RobotsCache {
+    pub fn new(config: Arc<Config>, http_client: HttpClient) -> Self {
+        Self {
+            user_agent: format!("{} {}", config.bot_name, config.user_agent),
+            http_client,
+            entries: Mutex::new(HashMap::new()),
+            last_access: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn robots_for(&self, url: &Url) -> Arc<RobotsTxt> {
+        let origin = url.origin().ascii_serialization();
+        let entry = self.entries.lock().await.entry(origin).or_default().clone();
+
+        entry.get_or_init(|| async { Arc::new(self.fetch(url).await) }).await.clone()
+    }
+
+    async fn fetch(&self, url: &Url) -> RobotsTxt {
+        let robots_url = match url.join("/robots.txt") {
+            Ok(robots_url) => robots_url,
+            Err(_) => {
+                return RobotsTxt::allow_all();
+            }
+        };
+
+        debug!("Fetching {}", robots_url);
+
+        match self.http_client.fetch(robots_url.as_str()).await {
+            Ok(response) if response.status().is_success() => {
+                match response.text().await {
+                    Ok(body) => RobotsTxt::parse(&body),
+                    Err(e) => {
+                        warn!("Failed to read {}: {}, disallowing host", robots_url, e);
+                        RobotsTxt::disallow_all()
+                    }
+                }
+            }
+            Ok(response) if response.status().is_client_error() => RobotsTxt::allow_all(),
+            Ok(response) => {
+                warn!("{} returned {}, disallowing host", robots_url, response.status());
+                RobotsTxt::disallow_all()
+            }
+            Err(e) => {
+                warn!("Failed to fetch {}: {}, disallowing host", robots_url, e);
+                RobotsTxt::disallow_all()
+            }
+        }
+    }
+
+    pub async fn is_allowed(&self, url: &str) -> bool {
+        match Url::parse(url) {
+            Ok(parsed) => self.robots_for(&parsed).await.is_allowed(&self.user_agent, &parsed),
+            Err(_) => true,
+        }
+    }
+
+    pub async fn wait_crawl_delay(&self, url: &str) {
+        let Ok(parsed) = Url::parse(url) else {
+            return;
+        };
+        let Some(delay) = self.robots_for(&parsed).await.crawl_delay(&self.user_agent) else {
+            return;
+        };
+
+        let origin = parsed.origin().ascii_serialization();
+        let now = Instant::now();
+        let ready_at = {
+            let mut last_access = self.last_access.lock().await;
+            let ready_at = last_access
+                .get(&origin)
+                .map(|last| (*last + delay).max(now))
+                .unwrap_or(now);
+            last_access.insert(origin, ready_at);
+            ready_at
+        };
+
+        if ready_at > now {
+            debug!("Honoring crawl-delay of {:?} for {}", delay, url);
+            sleep(ready_at - now).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::TestServer;
+
+    const AGENT: &str = "linkedin Mozilla/5.0";
+
+    fn allowed(robots: &RobotsTxt, url: &str) -> bool {
+        robots.is_allowed(AGENT, &Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn longest_matching_rule_wins() {
+        let robots = RobotsTxt::parse(
+            "User-agent: *\nDisallow: /in/\nAllow: /in/public\nDisallow: /in/public/private"
+        );
+
+        assert!(allowed(&robots, "https://x.com/jobs"));
+        assert!(!allowed(&robots, "https://x.com/in/jane"));
+        assert!(allowed(&robots, "https://x.com/in/public/jane"));
+        assert!(!allowed(&robots, "https://x.com/in/public/private/jane"));
+    }
+
+    #[test]
+    fn allow_wins_ties() {
+        let robots = RobotsTxt::parse("User-agent: *\nDisallow: /page\nAllow: /page");
+        assert!(allowed(&robots, "https://x.com/page"));
+    }
+
+    #[test]
+    fn wildcards_and_end_anchors() {
+        let robots = RobotsTxt::parse(
+            "User-agent: *\nDisallow: /*.json$\nDisallow: /search*q=\nDisallow: /exact$"
+        );
+
+        assert!(!allowed(&robots, "https://x.com/data/file.json"));
+        assert!(allowed(&robots, "https://x.com/data/file.json?v=1"));
+        assert!(!allowed(&robots, "https://x.com/search/results?q=rust"));
+        assert!(allowed(&robots, "https://x.com/search/results"));
+        assert!(!allowed(&robots, "https://x.com/exact"));
+        assert!(allowed(&robots, "https://x.com/exact/more"));
+    }
+
+    #[test]
+    fn specific_agent_group_replaces_wildcard_group() {
+        let robots = RobotsTxt::parse(
+            "User-agent: *\nDisallow: /\n\nUser-agent: LinkedIn\nDisallow: /private\nCrawl-delay: 2"
+        );
+
+        assert!(allowed(&robots, "https://x.com/jobs"));
+        assert!(!allowed(&robots, "https://x.com/private"));
+        assert!(!robots.is_allowed("otherbot", &Url::parse("https://x.com/jobs").unwrap()));
+        assert_eq!(robots.crawl_delay(AGENT), Some(Duration::from_secs(2)));
+        assert_eq!(robots.crawl_delay("otherbot"), None);
+    }
+
+    #[test]
+    fn crawl_delay_accepts_fractions_and_ignores_garbage() {
+        let robots = RobotsTxt::parse("User-agent: *\nCrawl-delay: 0.5");
+        assert_eq!(robots.crawl_delay(AGENT), Some(Duration::from_millis(500)));
+
+        let robots = RobotsTxt::parse("User-agent: *\nCrawl-delay: soon");
+        assert_eq!(robots.crawl_delay(AGENT), None);
+    }
+
+    #[test]
+    fn robots_txt_itself_is_always_allowed() {
+        let robots = RobotsTxt::parse("User-agent: *\nDisallow: /");
+        assert!(allowed(&robots, "https://x.com/robots.txt"));
+        assert!(!allowed(&robots, "https://x.com/"));
+    }
+
+    #[tokio::test]
+    async fn cache_fetches_robots_txt_once_per_host() {
+        let server = TestServer::start(|request_line| {
+            if request_line.starts_with("GET /robots.txt ") {
+                (200, "User-agent: *\nDisallow: /private\nCrawl-delay: 1".to_string())
+            } else {
+                (404, String::new())
+            }
+        }).await;
+
+        let config = Arc::new(Config::default());
+        let cache = RobotsCache::new(config.clone(), HttpClient::new(config).unwrap());
+        let public = format!("{}/public", server.url);
+        let private = format!("{}/private/page", server.url);
+
+        let (public_allowed, private_allowed) = tokio::join!(
+            cache.is_allowed(&public),
+            cache.is_allowed(&private)
+        );
+        assert!(public_allowed);
+        assert!(!private_allowed);
+
+        let robots = cache.robots_for(&Url::parse(&public).unwrap()).await;
+        assert_eq!(robots.crawl_delay(&cache.user_agent), Some(Duration::from_secs(1)));
+        assert_eq!(server.requests(), vec!["GET /robots.txt HTTP/1.1".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn missing_robots_txt_allows_everything() {
+        let server = TestServer::start(|_| (404, String::new())).await;
+        let config = Arc::new(Config::default());
+        let cache = RobotsCache::new(config.clone(), HttpClient::new(config).unwrap());
+
+        assert!(cache.is_allowed(&format!("{}/anything", server.url)).await);
+    }
+}
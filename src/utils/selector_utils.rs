@@ -1,37 +1,128 @@
-use scraper::Selector;
+use tracing::warn;
+use std::sync::{ Arc, Mutex };
+use std::collections::BTreeMap;
+use anyhow::{ anyhow, Result };
+use scraper::{ ElementRef, Selector };
+use serde::{ Deserialize, Serialize };
 
-pub enum SelectorInput<'a> {
-    Single(&'a str),
-    Multiple(&'a [&'a str]),
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged, expecting = "a selector string or a list of selector strings")]
+pub enum SelectorSpec {
+    Single(String),
+    Chain(Vec<String>),
 }
 
-impl<'a> From<&'a str> for SelectorInput<'a> {
-    fn from(selector_str: &'a str) -> Self {
-        SelectorInput::Single(selector_str)
+impl SelectorSpec {
+    pub fn alternatives(&self) -> &[String] {
+        match self {
+            SelectorSpec::Single(selector) => std::slice::from_ref(selector),
+            SelectorSpec::Chain(selectors) => selectors,
+        }
+    }
+
+    pub fn compile(&self) -> Result<Vec<Selector>> {
+        if self.alternatives().is_empty() {
+            return Err(anyhow!("empty selector chain"));
+        }
+
+        self.alternatives()
+            .iter()
+            .map(|selector| {
+                Selector::parse(selector).map_err(|e| anyhow!("{selector:?}: {e}"))
+            })
+            .collect()
     }
 }
 
-impl<'a> From<&'a [&'a str]> for SelectorInput<'a> {
-    fn from(selector_strs: &'a [&'a str]) -> Self {
-        SelectorInput::Multiple(selector_strs)
+impl From<&str> for SelectorSpec {
+    fn from(selector: &str) -> Self {
+        SelectorSpec::Single(selector.to_string())
     }
 }
 
-pub fn parse_selector<'a, T>(input: T) -> Selector where T: Into<SelectorInput<'a>> {
-    let selector_input = input.into();
-    match selector_input {
-        SelectorInput::Single(selector_str) => {
-            Selector::parse(selector_str).unwrap_or_else(|err| {
-                panic!("Failed to parse selector '{selector_str}': {err:?}");
-            })
+impl<const N: usize> From<[&str; N]> for SelectorSpec {
+    fn from(selectors: [&str; N]) -> Self {
+        SelectorSpec::Chain(selectors.iter().map(|selector| selector.to_string()).collect())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SelectorHits {
+    counts: Mutex<BTreeMap<String, u64>>,
+}
+
+impl SelectorHits {
+    fn record(&self, field: &str, hit: Option<(usize, &str)>) {
+        let key = match hit {
+            Some((0, _)) => format!("{field}/primary"),
+            Some((index, _)) => format!("{field}/fallback_{index}"),
+            None => format!("{field}/miss"),
+        };
+
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts.entry(key).or_insert(0);
+        *count += 1;
+
+        if let Some((index, selector)) = hit {
+            if index > 0 && *count == 1 {
+                warn!("Primary selector for {field} did not match, fell back to {selector:?}");
+            }
+        }
+    }
+
+    pub fn report(&self) -> BTreeMap<String, u64> {
+        self.counts.lock().unwrap().clone()
+    }
+
+    pub fn fallback_fields(&self) -> BTreeMap<String, u64> {
+        let mut fields = BTreeMap::new();
+        for (key, count) in self.counts.lock().unwrap().iter() {
+            if let Some((field, _)) = key.split_once("/fallback_") {
+                *fields.entry(field.to_string()).or_insert(0) += count;
+            }
         }
-        SelectorInput::Multiple(selector_strs) => {
-            for selector_str in selector_strs {
-                if let Ok(selector) = Selector::parse(selector_str) {
-                    return selector;
-                }
+        fields
+    }
+}
+
+pub struct SelectorChain {
+    field: String,
+    alternatives: Vec<(String, Selector)>,
+    hits: Arc<SelectorHits>,
+}
+
+impl SelectorChain {
+    pub fn new(field: &str, spec: &SelectorSpec, hits: Arc<SelectorHits>) -> Result<Self> {
+        let compiled = spec.compile().map_err(|e| anyhow!("Invalid selector for {field}: {e}"))?;
+        Ok(Self {
+            field: field.to_string(),
+            alternatives: spec.alternatives().iter().cloned().zip(compiled).collect(),
+            hits,
+        })
+    }
+
+    pub fn first<'a>(&self, element: ElementRef<'a>) -> Option<ElementRef<'a>> {
+        for (index, (source, selector)) in self.alternatives.iter().enumerate() {
+            if let Some(found) = element.select(selector).next() {
+                self.hits.record(&self.field, Some((index, source)));
+                return Some(found);
+            }
+        }
+
+        self.hits.record(&self.field, None);
+        None
+    }
+
+    pub fn all<'a>(&self, element: ElementRef<'a>) -> Vec<ElementRef<'a>> {
+        for (index, (source, selector)) in self.alternatives.iter().enumerate() {
+            let found: Vec<ElementRef<'a>> = element.select(selector).collect();
+            if !found.is_empty() {
+                self.hits.record(&self.field, Some((index, source)));
+                return found;
             }
-            panic!("Failed to parse any of the provided selectors: {selector_strs:?}");
         }
+
+        self.hits.record(&self.field, None);
+        Vec::new()
     }
 }
@@ -0,0 +1,173 @@
+use std::fs;
+use url::Url;
+use std::path::Path;
+use reqwest::cookie::Jar;
+use crate::config::Config;
+use anyhow::{ bail, Context, Result };
+
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    cookies: Vec<(String, String)>,
+}
+
+impl Session {
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let mut session = match &config.cookie_file {
+            Some(path) => Self::from_cookie_file(path)?,
+            None => Self::default(),
+        };
+
+        if let Some(li_at) = &config.li_at {
+            session.set_cookie("li_at", li_at);
+        }
+
+        if let Some(jsessionid) = &config.jsessionid {
+            session.set_cookie("JSESSIONID", jsessionid);
+        }
+
+        if session.cookies.is_empty() {
+            return Ok(None);
+        }
+
+        if session.cookie("li_at").is_none() {
+            bail!("Authenticated session requires an li_at cookie");
+        }
+
+        Ok(Some(session))
+    }
+
+    pub fn from_cookie_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs
+            ::read_to_string(path)
+            .with_context(|| format!("Failed to read cookie file {}", path.display()))?;
+        Self::parse_cookie_file(&content, &path.display().to_string())
+    }
+
+    fn parse_cookie_file(content: &str, source: &str) -> Result<Self> {
+        let mut session = Self::default();
+        for (line_number, line) in content.lines().enumerate() {
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 7 {
+                bail!(
+                    "Invalid Netscape cookie at {}:{}, expected 7 tab-separated fields",
+                    source,
+                    line_number + 1
+                );
+            }
+
+            if Self::is_linkedin_host(fields[0].trim_start_matches('.')) {
+                session.set_cookie(fields[5], fields[6]);
+            }
+        }
+
+        Ok(session)
+    }
+
+    pub fn is_linkedin_host(host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        host == "linkedin.com" || host.ends_with(".linkedin.com")
+    }
+
+    fn set_cookie(&mut self, name: &str, value: &str) {
+        let value = value.trim().to_string();
+        match self.cookies.iter_mut().find(|(existing, _)| existing == name) {
+            Some(cookie) => {
+                cookie.1 = value;
+            }
+            None => self.cookies.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn install(&self, jar: &Jar) {
+        let url = Url::parse("https://www.linkedin.com/").expect("Invalid LinkedIn URL");
+        for (name, value) in &self.cookies {
+            jar.add_cookie_str(&format!("{name}={value}; Domain=.linkedin.com; Path=/"), &url);
+        }
+    }
+
+    pub fn cookie_header(&self) -> String {
+        self.cookies
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    pub fn csrf_token(&self) -> Option<String> {
+        self.cookie("JSESSIONID").map(|jsessionid| jsessionid.replace('"', ""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOKIES: &str = "\
+# Netscape HTTP Cookie File
+.linkedin.com\tTRUE\t/\tTRUE\t1767225600\tli_at\tAQEDAT
+#HttpOnly_.www.linkedin.com\tTRUE\t/\tTRUE\t1767225600\tJSESSIONID\t\"ajax:123\"
+
+.example.com\tTRUE\t/\tFALSE\t1767225600\tli_at\tforeign
+.notlinkedin.com\tTRUE\t/\tFALSE\t1767225600\tli_at\tlookalike
+evil-linkedin.com\tFALSE\t/\tFALSE\t1767225600\tJSESSIONID\tlookalike
+";
+
+    #[test]
+    fn parses_linkedin_cookies_including_http_only_lines() {
+        let session = Session::parse_cookie_file(COOKIES, "cookies.txt").unwrap();
+        assert_eq!(session.cookie("li_at"), Some("AQEDAT"));
+        assert_eq!(session.cookie("JSESSIONID"), Some("\"ajax:123\""));
+        assert_eq!(session.csrf_token().as_deref(), Some("ajax:123"));
+    }
+
+    #[test]
+    fn ignores_cookies_for_other_and_lookalike_domains() {
+        let session = Session::parse_cookie_file(COOKIES, "cookies.txt").unwrap();
+        assert_eq!(session.cookie_header(), "li_at=AQEDAT; JSESSIONID=\"ajax:123\"");
+    }
+
+    #[test]
+    fn rejects_lines_without_seven_fields() {
+        let error = Session::parse_cookie_file(".linkedin.com\tTRUE\tli_at", "cookies.txt")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("cookies.txt:1"), "{error}");
+    }
+
+    #[test]
+    fn config_entries_override_the_cookie_file() {
+        let file_name = format!("in-scraper-cookies-{}.txt", std::process::id());
+        let path = std::env::temp_dir().join(file_name);
+        fs::write(&path, COOKIES).unwrap();
+        let config = Config {
+            cookie_file: Some(path.display().to_string()),
+            li_at: Some("from-config".to_string()),
+            ..Config::default()
+        };
+
+        let session = Session::from_config(&config).unwrap().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(session.cookie("li_at"), Some("from-config"));
+        assert_eq!(session.cookie("JSESSIONID"), Some("\"ajax:123\""));
+    }
+
+    #[test]
+    fn session_requires_li_at() {
+        let config = Config { jsessionid: Some("ajax:123".to_string()), ..Config::default() };
+        assert!(Session::from_config(&config).is_err());
+        assert!(Session::from_config(&Config::default()).unwrap().is_none());
+    }
+}
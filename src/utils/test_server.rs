@@ -0,0 +1,52 @@
+use std::sync::{ Arc, Mutex };
+use tokio::net::TcpListener;
+use tokio::io::{ AsyncReadExt, AsyncWriteExt };
+
+pub struct TestServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestServer {
+    pub async fn start(respond: impl Fn(&str) -> (u16, String) + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let respond = Arc::new(respond);
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                let respond = respond.clone();
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut buffer = [0; 1024];
+                    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                        match stream.read(&mut buffer).await {
+                            Ok(0) | Err(_) => {
+                                return;
+                            }
+                            Ok(read) => head.extend_from_slice(&buffer[..read]),
+                        }
+                    }
+
+                    let head = String::from_utf8_lossy(&head).to_string();
+                    let request_line = head.lines().next().unwrap_or_default().to_string();
+                    recorded.lock().unwrap().push(request_line.clone());
+
+                    let (status, body) = respond(&request_line);
+                    let head = format!("HTTP/1.1 {status} Test\r\nConnection: close\r\n");
+                    let response = format!("{head}Content-Length: {}\r\n\r\n{body}", body.len());
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}